@@ -0,0 +1,53 @@
+//! Unofficial ScarDoc generator, made by the Battlegrounds Development Team.
+//!
+//! The library reads scardoc comments from scar source files and game dumps into a [`ScarDoc`], which can then be
+//! merged with other scardocs, saved as JSON or exported to editor tooling.
+//!
+//! ```no_run
+//! use coh3_scardoc_gen::{ScarDocError, generate_scardoc, merge_scardoc, load_scardoc_from_json, save_scardoc_to_json};
+//!
+//! fn main() -> Result<(), ScarDocError> {
+//!     let generated = generate_scardoc("scar")?;
+//!     let manual = load_scardoc_from_json(String::from("scardoc_manual.json"))?;
+//!     save_scardoc_to_json(&merge_scardoc(&generated, &manual)?, "scardoc.json")
+//! }
+//! ```
+//!
+//! All fallible functions return a [`ScarDocError`] describing what failed and in which file.
+
+pub mod scardoc;
+pub mod scarfile;
+pub mod scarcache;
+pub mod scarfilter;
+pub mod scarenum;
+pub mod scartype;
+pub mod scardocmerger;
+pub mod scardump;
+pub mod scarluals;
+pub mod scarsnippets;
+pub mod scarlsp;
+pub mod scarwatch;
+pub mod scarconfig;
+pub mod scarcategory;
+pub mod scarschema;
+pub mod scarlint;
+pub mod scarmigration;
+pub mod scarerror;
+
+pub use scarerror::ScarDocError;
+pub use scardoc::{ScarDoc, ScarDocCategory, ScarGlobal, SortOrder, GenerateOptions, SCARDOC_FORMAT_VERSION, generate_scardoc, generate_scardoc_with_options, categorise_functions, categorise_functions_with, load_scardoc_from_json, save_scardoc_to_json};
+pub use scarfile::{ScarSourceFile, ScarFunction, ScarParameter, ScarDeprecation, get_scar_sourcefile};
+pub use scarcache::ScarCache;
+pub use scarfilter::ScarFileFilter;
+pub use scarenum::{ScarEnum, ScarEnumValue};
+pub use scartype::{ScarType, ScarTypeField};
+pub use scardocmerger::{MergePolicy, merge_scardoc, merge_scardocs, merge_scardoc_with_policy, merge_scardocs_with_policy, merge_scardoc_with, merge_scardocs_with};
+pub use scardump::read_scardump;
+pub use scarluals::export_luals;
+pub use scarsnippets::export_snippets;
+pub use scarlsp::ScarLanguageServer;
+pub use scarwatch::{WatchOptions, watch_scardoc};
+pub use scarconfig::{ScarDocConfig, build_scardoc};
+pub use scarcategory::CategoryRules;
+pub use scarschema::{get_scardoc_schema, save_scardoc_schema, validate_scardoc, validate_scardoc_file};
+pub use scarlint::{ScarDiagnostic, lint_scardoc, lint_references, lint_deprecated_calls, lint_signatures, lint_scar_files};
@@ -1,18 +1,46 @@
-use std::{env, fs::File, io::{Write, BufReader}, path::Path};
+use std::{env, path::{Path, PathBuf}};
 
-use scardoc::ScarDoc;
-
-mod scardoc;
-mod scarfile;
-mod scarenum;
-mod scardocmerger;
-mod scardump;
+use coh3_scardoc_gen::{scardoc::{self, ScarDoc, SortOrder, GenerateOptions, load_scardoc_from_json, save_scardoc_to_json}, scarfilter::ScarFileFilter, scardocmerger::{self, MergePolicy}, scardump, scarluals, scarsnippets, scarlsp, scarschema, scarlint::{self, ScarDiagnostic}, scarwatch::{self, WatchOptions}, scarconfig::{self, ScarDocConfig, SCARDOC_CONFIG_FILE}, scarcategory::CategoryRules};
 
 fn main() {
-    
-    println!("Scardoc generator");
 
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+
+    let order = if args.iter().any(|x| x == "--source-order") { SortOrder::Source } else { SortOrder::Name };
+    args.retain(|x| x != "--source-order");
+    let jobs = match take_option(&mut args, "-j").map(|x| x.parse::<usize>()) {
+        None => 0,
+        Some(Ok(n)) => n,
+        Some(Err(_)) => {
+            eprintln!("Invalid job count given to -j");
+            return;
+        }
+    };
+    let cache = if args.iter().any(|x| x == "--no-cache") { None } else { Some(PathBuf::from("scardoc.cache.json")) };
+    args.retain(|x| x != "--no-cache");
+    let cache = take_option(&mut args, "--cache").map(PathBuf::from).or(cache);
+    let filter = ScarFileFilter {
+        include: take_options(&mut args, "--include"),
+        exclude: take_options(&mut args, "--exclude"),
+        extensions: take_options(&mut args, "--ext")
+    };
+    let categories = match take_option(&mut args, "--categories").map(CategoryRules::load) {
+        None => CategoryRules::default(),
+        Some(Ok(rules)) => rules,
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    let options = GenerateOptions { order, jobs, cache, filter, categories };
+
+    // The language server talks over stdout, so nothing else may be printed to it
+    if args.get(1).is_some_and(|x| x == "lsp") {
+        main_lsp(&args[2..], &options.filter);
+        return;
+    }
+
+    println!("Scardoc generator");
 
     match args.as_slice() {
         [_, flag, ..] if flag == "-m" => {
@@ -21,62 +49,269 @@ fn main() {
                 println!("Please provide at least two files to merge.");
                 return;
             }
-            main_merge_scardocs(paths)
-        }
-        [_, dir_path] => {
-            main_generate_scardoc(dir_path.clone());
+            main_merge_scardocs(paths, &options)
         }
         [_, flag, dir_path] if flag == "-g" => {
-            main_generate_scardoc(dir_path.clone());
+            main_generate_scardoc(dir_path.clone(), &options);
         }
         [_, flag, file_path] if flag == "-d" => {
-            main_generate_scardoc_from_dump(file_path.clone());
+            main_generate_scardoc_from_dump(file_path.clone(), order);
+        }
+        [_, flag, file_path] if flag == "-l" => {
+            main_export_luals(file_path.clone(), String::from("luals"));
+        }
+        [_, flag, file_path, out_dir] if flag == "-l" => {
+            main_export_luals(file_path.clone(), out_dir.clone());
+        }
+        [_, cmd, dir_path, exports @ ..] if cmd == "watch" => {
+            main_watch_scardoc(dir_path.clone(), exports, &options);
+        }
+        [_, cmd] if cmd == "build" => {
+            main_build_scardoc(String::from(SCARDOC_CONFIG_FILE));
+        }
+        [_, cmd, config_file] if cmd == "build" => {
+            main_build_scardoc(config_file.clone());
+        }
+        [_, cmd] if cmd == "schema" => {
+            main_save_schema(String::from("scardoc.schema.json"));
+        }
+        [_, cmd, out_file] if cmd == "schema" => {
+            main_save_schema(out_file.clone());
+        }
+        [_, cmd, file_path] if cmd == "validate" => {
+            main_validate_scardoc(file_path.clone());
+        }
+        [_, cmd, file_path] if cmd == "lint" => {
+            main_lint_scardoc(file_path.clone(), None, &options.filter);
+        }
+        [_, cmd, file_path, dir_path] if cmd == "lint" => {
+            main_lint_scardoc(file_path.clone(), Some(dir_path.clone()), &options.filter);
+        }
+        [_, flag, file_path] if flag == "-s" => {
+            main_export_snippets(file_path.clone(), String::from("scar.code-snippets"));
+        }
+        [_, flag, file_path, out_file] if flag == "-s" => {
+            main_export_snippets(file_path.clone(), out_file.clone());
+        }
+        [_, dir_path] => {
+            main_generate_scardoc(dir_path.clone(), &options);
         }
         _ => {
             println!("Invalid arguments. Usage: \n\
+                     build [scardoc.toml] \n\
                      -m file1.json file2.json, ... \n\
-                     [-g] path/to/some/dir");
+                     -l scardoc.json [output/dir] \n\
+                     -s scardoc.json [output.code-snippets] \n\
+                     lsp scardoc.json [path/to/scar/dir] \n\
+                     watch path/to/some/dir [-l output/dir] [-s output.code-snippets] \n\
+                     schema [scardoc.schema.json] \n\
+                     validate scardoc.json \n\
+                     lint scardoc.json [path/to/scar/dir] \n\
+                     [-g] path/to/some/dir \n\
+                     Add --source-order to keep declaration order instead of sorting by name \n\
+                     Add -j <count> to limit the amount of threads used to parse scar files \n\
+                     Add --cache <file> to change where parsed scar files are cached (scardoc.cache.json), or --no-cache to disable it \n\
+                     Add --include <glob> or --exclude <glob> to select scar files, and --ext <extension> to also read files like .lua \n\
+                     Add --categories <rules.toml> to categorise functions using a category rules file");
         }
     }
 
 }
 
-fn main_generate_scardoc_from_dump(dump_file: String) {
+/// Removes an option and its value from the arguments, returning the value.
+fn take_option(args: &mut Vec<String>, option: &str) -> Option<String> {
+    let idx = args.iter().position(|x| x == option)?;
+    args.remove(idx);
+    if idx < args.len() {
+        Some(args.remove(idx))
+    } else {
+        Some(String::new())
+    }
+}
+
+/// Removes every occurrence of an option and its value from the arguments, returning the values.
+fn take_options(args: &mut Vec<String>, option: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    while let Some(value) = take_option(args, option) {
+        values.push(value);
+    }
+    values
+}
+
+fn main_generate_scardoc_from_dump(dump_file: String, order: SortOrder) {
     match scardump::read_scardump(dump_file) {
         Err(e) => eprintln!("{}", e),
-        Ok(doc) => {
-            match save_to_json(&doc, "dump_scardoc.json") {
-                Err(e) => eprintln!("{}", e),
-                Ok(_) => {}
+        Ok(mut doc) => {
+            doc.sort(order);
+            if let Err(e) = save_scardoc_to_json(&doc, "dump_scardoc.json") {
+                eprintln!("{}", e)
             }
         }
     }
 }
 
-fn main_generate_scardoc(dir_path: String) {
+fn main_generate_scardoc(dir_path: String, options: &GenerateOptions) {
     println!("Generating scardoc for directory: {}", dir_path);
-    match scardoc::generate_scardoc(dir_path) {
+    match scardoc::generate_scardoc_with_options(dir_path, options) {
         Err(e) => eprintln!("{}", e),
         Ok(doc) => {
             println!("Loaded scardoc");
-            match save_to_json(&doc, "scardoc.json") {
+            match save_scardoc_to_json(&doc, "scardoc.json") {
                 Err(e) => eprintln!("{}", e),
                 Ok(_) => {
-                    println!("Saved scardoc to {}", "scardoc.json");
+                    println!("Saved scardoc to scardoc.json");
+                }
+            }
+        }
+    }
+}
+
+fn main_build_scardoc(config_file: String) {
+    println!("Building scardoc from {}", config_file);
+    let config = match ScarDocConfig::load(&config_file) {
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+        Ok(config) => config
+    };
+    match scarconfig::build_scardoc(&config) {
+        Err(e) => eprintln!("{}", e),
+        Ok(doc) => {
+            println!("Saved scardoc to {}", config.output.display());
+            let mut diagnostics = scarlint::lint_scardoc(&doc);
+            match config.get_scar_files().and_then(|x| scarlint::lint_scar_files(&doc, &x)) {
+                Err(e) => eprintln!("{}", e),
+                Ok(calls) => diagnostics.extend(calls)
+            }
+            print_diagnostics(&diagnostics);
+        }
+    }
+}
+
+fn main_watch_scardoc(dir_path: String, exports: &[String], options: &GenerateOptions) {
+    let mut watch = WatchOptions { generate: options.clone(), ..Default::default() };
+    for export in exports.chunks(2) {
+        match export {
+            [flag, out] if flag == "-l" => watch.luals = Some(out.into()),
+            [flag, out] if flag == "-s" => watch.snippets = Some(out.into()),
+            _ => {
+                eprintln!("Invalid export '{}', expected -l output/dir or -s output.code-snippets", export.join(" "));
+                return;
+            }
+        }
+    }
+    if let Err(e) = scarwatch::watch_scardoc(dir_path, &watch) {
+        eprintln!("{}", e)
+    }
+}
+
+fn main_export_luals(file_path: String, out_dir: String) {
+    match load_scardoc_from_json(file_path) {
+        Err(e) => eprintln!("{}", e),
+        Ok(doc) => match scarluals::export_luals(&doc, &out_dir) {
+            Err(e) => eprintln!("{}", e),
+            Ok(_) => {
+                println!("Saved Lua Language Server definitions to {}", out_dir);
+            }
+        }
+    }
+}
+
+fn main_export_snippets(file_path: String, out_file: String) {
+    match load_scardoc_from_json(file_path) {
+        Err(e) => eprintln!("{}", e),
+        Ok(doc) => match scarsnippets::export_snippets(&doc, &out_file) {
+            Err(e) => eprintln!("{}", e),
+            Ok(_) => {
+                println!("Saved snippets to {}", out_file);
+            }
+        }
+    }
+}
+
+fn main_lsp(args: &[String], filter: &ScarFileFilter) {
+    let doc = match args.first() {
+        None => ScarDoc::default(),
+        Some(file_path) => match load_scardoc_from_json(file_path.clone()) {
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+            Ok(doc) => doc
+        }
+    };
+    let mut server = scarlsp::ScarLanguageServer::new(doc);
+    if let Some(dir_path) = args.get(1) {
+        server.watch_directory(dir_path.clone(), filter);
+    }
+    if let Err(e) = server.run() {
+        eprintln!("{}", e)
+    }
+}
+
+fn main_save_schema(out_file: String) {
+    match scarschema::save_scardoc_schema(&out_file) {
+        Err(e) => eprintln!("{}", e),
+        Ok(_) => {
+            println!("Saved scardoc schema to {}", out_file);
+        }
+    }
+}
+
+fn main_validate_scardoc(file_path: String) {
+    match scarschema::validate_scardoc_file(&file_path) {
+        Err(e) => eprintln!("{}", e),
+        Ok(errors) if errors.is_empty() => println!("{} is a valid scardoc", file_path),
+        Ok(errors) => {
+            eprintln!("{} is not a valid scardoc:", file_path);
+            for error in errors {
+                eprintln!("\t{}", error);
+            }
+        }
+    }
+}
+
+fn main_lint_scardoc(file_path: String, dir_path: Option<String>, filter: &ScarFileFilter) {
+    match load_scardoc_from_json(file_path.clone()) {
+        Err(e) => eprintln!("{}", e),
+        Ok(doc) => {
+            let mut diagnostics = scarlint::lint_scardoc(&doc);
+            // Signatures and calls to deprecated functions can only be checked in the scar code itself
+            if let Some(dir_path) = dir_path {
+                match scardoc::get_filtered_scar_files(&dir_path, filter).and_then(|x| scarlint::lint_scar_files(&doc, &x)) {
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        return;
+                    }
+                    Ok(calls) => diagnostics.extend(calls)
                 }
             }
+            if diagnostics.is_empty() {
+                println!("Found no problems in {}", file_path);
+                return;
+            }
+            print_diagnostics(&diagnostics);
+            // Lets CI fail on undocumented or misspelled types
+            std::process::exit(1);
         }
     }
 }
 
-fn save_to_json(doc: &scardoc::ScarDoc, filepath: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let json = serde_json::to_string_pretty(doc)?;
-    let mut file = File::create(filepath)?;
-    file.write_all(json.as_bytes())?;
-    Ok(())
+fn print_diagnostics(diagnostics: &[ScarDiagnostic]) {
+    for diagnostic in diagnostics {
+        eprintln!("warning: {}", diagnostic);
+    }
 }
 
-fn main_merge_scardocs(scardocs: &[String]) {
+fn main_merge_scardocs(scardocs: &[String], options: &GenerateOptions) {
+    let categoriser = match options.categories.get_categoriser() {
+        Ok(categoriser) => categoriser,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
     let mut docs = Vec::new();
     for scardoc in scardocs {
         if !Path::new(&scardoc).exists() {
@@ -85,43 +320,38 @@ fn main_merge_scardocs(scardocs: &[String]) {
         }
         match load_scardoc_from_json(scardoc.to_string()) {
             Ok(s) => { docs.push(s); println!("Loaded scardoc {}", scardoc) },
-            Err(e) => eprintln!("{}",e)
+            Err(e) => {
+                eprintln!("{}",e);
+                return;
+            }
+        }
+        match scarschema::validate_scardoc_file(scardoc) {
+            Ok(errors) if !errors.is_empty() => {
+                eprintln!("Scardoc '{}' is not valid and won't be merged:", scardoc);
+                for error in errors {
+                    eprintln!("\t{}", error);
+                }
+                return;
+            }
+            _ => {}
         }
     }
     if docs.len() <= 1 {
         println!("Not enough scardocs to merge");
         return;
     }
-    let mut first = docs.get(0).unwrap();
-    let mut result: Option<ScarDoc> = None;
-    for i in 1..docs.len() {
-        let second = docs.get(i).unwrap();
-        let new_doc = scardocmerger::merge_scardoc(first, second);
-        result = Some(new_doc);
-        first = second
-    }
-    match result {
-        None => eprintln!("Failed generating merged scardocs"),
-        Some(s) => match save_to_json(&s, "merged_scardoc.json") {
-            Err(e) => eprintln!("{}", e),
-            Ok(_) => {
-                println!("Saved merged scardoc to {}", "merged_scardoc.json");
+    match scardocmerger::merge_scardocs_with(&docs, MergePolicy::Override, &categoriser) {
+        Err(e) => eprintln!("Failed generating merged scardocs: {}", e),
+        Ok(mut s) => {
+            s.sort(options.order);
+            // References may point to something documented by another scardoc, so they're only checked once merged
+            print_diagnostics(&scarlint::lint_references(&s));
+            match save_scardoc_to_json(&s, "merged_scardoc.json") {
+                Err(e) => eprintln!("{}", e),
+                Ok(_) => {
+                    println!("Saved merged scardoc to merged_scardoc.json");
+                }
             }
         }
     }
 }
-
-fn load_scardoc_from_json(file_path: String) -> Result<scardoc::ScarDoc, Box<dyn std::error::Error>> {
-
-    let file = File::open(&file_path);
-    if file.is_err() {
-        return Err(Box::new(file.err().unwrap()))
-    }
-
-    let reader = BufReader::new(file.unwrap());
-
-    match serde_json::from_reader(reader) {
-        Err(e) => Err(Box::new(e)),
-        Ok(s) => Ok(s)
-    }
-}
@@ -0,0 +1,189 @@
+use std::{collections::BTreeMap, fs, path::Path, time::UNIX_EPOCH};
+use serde::{Serialize, Deserialize};
+
+use crate::scardoc::{SCARDOC_FORMAT_VERSION, ScarGlobal, read_scar_files_with};
+use crate::scarerror::ScarDocError;
+use crate::scarfile::{ScarSourceFile, ScarFunction, get_scar_sourcefile_from_reader};
+use crate::scarenum::ScarEnum;
+use crate::scartype::ScarType;
+
+/// The version of the cache format.
+///
+/// Must be bumped whenever the parser may read an unchanged scar file differently, so older caches are thrown away.
+pub const SCAR_CACHE_VERSION: u32 = 19;
+
+const GENERATOR_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The scar files read by a previous run, used to only reparse the files that changed since.
+#[derive(Serialize, Deserialize)]
+pub struct ScarCache {
+    #[serde(default)]
+    pub cache_version: u32,
+    #[serde(default)]
+    pub format_version: u32,
+    #[serde(default)]
+    pub generator_version: String,
+    #[serde(default)]
+    pub files: BTreeMap<String, ScarCacheEntry>
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScarCacheEntry {
+    pub size: u64,
+    /// Nanoseconds since the unix epoch, 0 if the file system doesn't record it.
+    pub modified: u64,
+    /// FNV-1a hash of the file content.
+    pub hash: u64,
+    pub functions: Vec<ScarFunction>,
+    #[serde(default)]
+    pub types: Vec<ScarType>,
+    #[serde(default)]
+    pub globals: Vec<ScarGlobal>,
+    #[serde(default)]
+    pub enums: Vec<ScarEnum>
+}
+
+impl Default for ScarCache {
+    fn default() -> Self {
+        ScarCache {
+            cache_version: SCAR_CACHE_VERSION,
+            format_version: SCARDOC_FORMAT_VERSION,
+            generator_version: GENERATOR_VERSION.to_string(),
+            files: BTreeMap::new()
+        }
+    }
+}
+
+impl ScarCache {
+
+    /// Loads a cache, starting with an empty one if it's missing, unreadable or was written by another version of the generator.
+    pub fn load<P: AsRef<Path>>(cache_file: P) -> ScarCache {
+        let cache = fs::read(cache_file.as_ref())
+            .ok()
+            .and_then(|x| serde_json::from_slice::<ScarCache>(&x).ok());
+        match cache {
+            Some(cache) if cache.is_current() => cache,
+            _ => ScarCache::default()
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, cache_file: P) -> Result<(), ScarDocError> {
+        let path = cache_file.as_ref().display();
+        let json = serde_json::to_string(self).map_err(|e| ScarDocError::json(&path, e))?;
+        fs::write(cache_file.as_ref(), json).map_err(|e| ScarDocError::io(&path, e))
+    }
+
+    fn is_current(&self) -> bool {
+        self.cache_version == SCAR_CACHE_VERSION && self.format_version == SCARDOC_FORMAT_VERSION && self.generator_version == GENERATOR_VERSION
+    }
+
+    /// Reads a scar file, reusing the cached functions, types, globals and enums if its size and mtime, or its content, are unchanged.
+    ///
+    /// Returns the file along with its up to date cache entry. Warnings are only reported for files that are reparsed.
+    pub fn read_scar_file(&self, file_path: &str) -> Result<(ScarSourceFile, ScarCacheEntry), ScarDocError> {
+        let metadata = fs::metadata(file_path).map_err(|e| ScarDocError::io(file_path, e))?;
+        let size = metadata.len();
+        let modified = metadata.modified()
+            .ok()
+            .and_then(|x| x.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |x| x.as_nanos() as u64);
+
+        let cached = self.files.get(file_path);
+        if let Some(entry) = cached.filter(|x| x.size == size && x.modified == modified && modified != 0) {
+            return Ok((get_cached_sourcefile(file_path, entry), entry.clone()));
+        }
+
+        // The file was touched, but its content may still be the same
+        let content = fs::read(file_path).map_err(|e| ScarDocError::io(file_path, e))?;
+        let hash = get_content_hash(&content);
+        let source = match cached.filter(|x| x.hash == hash) {
+            Some(entry) => get_cached_sourcefile(file_path, entry),
+            None => get_scar_sourcefile_from_reader(file_path.to_string(), content.as_slice())?
+        };
+
+        let entry = ScarCacheEntry {
+            size,
+            modified,
+            hash,
+            functions: source.functions.clone(),
+            types: source.types.clone(),
+            globals: source.globals.clone(),
+            enums: source.enums.clone()
+        };
+        Ok((source, entry))
+    }
+
+}
+
+/// Reads scar files like [`read_scar_files`](crate::scardoc::read_scar_files), reusing the cached files that haven't changed.
+///
+/// The cache is updated to only hold the files that were read successfully.
+pub fn read_scar_files_cached(files: &[String], jobs: usize, cache: &mut ScarCache) -> Vec<Result<ScarSourceFile, ScarDocError>> {
+    let results = read_scar_files_with(files, jobs, |file| cache.read_scar_file(file));
+    cache.files.clear();
+    results.into_iter()
+        .map(|x| x.map(|(source, entry)| {
+            cache.files.insert(source.source_name.clone(), entry);
+            source
+        }))
+        .collect()
+}
+
+fn get_cached_sourcefile(file_path: &str, entry: &ScarCacheEntry) -> ScarSourceFile {
+    ScarSourceFile {
+        source_name: file_path.to_string(),
+        functions: entry.functions.clone(),
+        types: entry.types.clone(),
+        globals: entry.globals.clone(),
+        enums: entry.enums.clone(),
+        warnings: Vec::new()
+    }
+}
+
+/// Gets the 64 bit FNV-1a hash of `content`.
+fn get_content_hash(content: &[u8]) -> u64 {
+    content.iter().fold(0xcbf29ce484222325, |hash, x| (hash ^ *x as u64).wrapping_mul(0x100000001b3))
+}
+
+mod tests {
+
+    #[test]
+    fn can_hash_content() {
+        assert_eq!(super::get_content_hash(b""), 0xcbf29ce484222325);
+        assert_eq!(super::get_content_hash(b"a"), 0xaf63dc4c8601ec8c);
+        assert_ne!(super::get_content_hash(b"ab"), super::get_content_hash(b"ba"));
+    }
+
+    #[test]
+    fn can_reuse_unchanged_files() {
+        let dir = std::env::temp_dir().join(format!("scardoc-cache-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("simple.scar").to_str().unwrap().to_string();
+        std::fs::copy("scar/simple.scar", &file).unwrap();
+
+        let mut cache = super::ScarCache::default();
+        let files = vec![file.clone()];
+        let first = super::read_scar_files_cached(&files, 1, &mut cache);
+        assert_eq!(first[0].as_ref().unwrap().functions[0].name, "Util_ScarPos");
+
+        // Unchanged files are taken from the cache rather than reparsed
+        cache.files.get_mut(&file).unwrap().functions[0].name = String::from("Cached");
+        let second = super::read_scar_files_cached(&files, 1, &mut cache);
+        assert_eq!(second[0].as_ref().unwrap().functions[0].name, "Cached");
+
+        // Touching a file without changing its content still uses the cache
+        cache.files.get_mut(&file).unwrap().modified = 1;
+        let third = super::read_scar_files_cached(&files, 1, &mut cache);
+        assert_eq!(third[0].as_ref().unwrap().functions[0].name, "Cached");
+
+        // Changed files are reparsed
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::write(&file, content.replace("Util_ScarPos", "Util_ScarPosition")).unwrap();
+        let fourth = super::read_scar_files_cached(&files, 1, &mut cache);
+        assert_eq!(fourth[0].as_ref().unwrap().functions[0].name, "Util_ScarPosition");
+        assert_eq!(cache.files.get(&file).unwrap().functions[0].name, "Util_ScarPosition");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+}
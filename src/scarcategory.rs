@@ -0,0 +1,194 @@
+use std::{collections::BTreeMap, fs, path::Path};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::scarerror::ScarDocError;
+use crate::scarfile::ScarFunction;
+
+/// Rules deciding which category each function is listed in, read from a rules file or the `[categories]` table of
+/// `scardoc.toml`.
+///
+/// Functions no rule applies to are categorised by the prefix of their name, before the first `_` or `:`.
+#[derive(Deserialize, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct CategoryRules {
+    /// Merges categories whose names only differ in case, like `Util` and `UTIL`.
+    pub fold_case: bool,
+    /// Rules tried in order, the first matching rule decides the category.
+    pub rules: Vec<CategoryRule>,
+    /// Categories listed under another name, like `Sgroup = "SGroup"`.
+    pub aliases: BTreeMap<String, String>,
+    /// The category of specific functions, taking precedence over every rule.
+    pub functions: BTreeMap<String, String>,
+    /// Descriptions of the categories, included in the scardoc.
+    pub descriptions: BTreeMap<String, String>
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CategoryRule {
+    /// Matches functions whose name starts with this prefix.
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// Matches functions whose name matches this regular expression.
+    #[serde(default)]
+    pub pattern: Option<String>,
+    pub category: String
+}
+
+/// [`CategoryRules`] ready to categorise functions.
+#[derive(Default)]
+pub struct Categoriser {
+    rules: Vec<(Option<String>, Option<Regex>, String)>,
+    fold_case: bool,
+    aliases: BTreeMap<String, String>,
+    functions: BTreeMap<String, String>,
+    descriptions: BTreeMap<String, String>
+}
+
+impl CategoryRules {
+
+    /// Loads category rules from a TOML file.
+    pub fn load<P: AsRef<Path>>(rules_file: P) -> Result<CategoryRules, ScarDocError> {
+        let path = rules_file.as_ref().display().to_string();
+        let content = fs::read_to_string(rules_file.as_ref()).map_err(|e| ScarDocError::io(&path, e))?;
+        toml::from_str(&content).map_err(|e| ScarDocError::Config { path, source: e })
+    }
+
+    pub fn get_categoriser(&self) -> Result<Categoriser, ScarDocError> {
+        let mut rules = Vec::new();
+        for rule in &self.rules {
+            let invalid = |message: String| ScarDocError::InvalidRule { category: rule.category.clone(), message };
+            if rule.prefix.is_none() && rule.pattern.is_none() {
+                return Err(invalid(String::from("a rule needs a prefix or a pattern")));
+            }
+            let pattern = match &rule.pattern {
+                None => None,
+                Some(pattern) => Some(Regex::new(pattern).map_err(|e| invalid(e.to_string()))?)
+            };
+            rules.push((rule.prefix.clone(), pattern, rule.category.clone()));
+        }
+        Ok(Categoriser {
+            rules,
+            fold_case: self.fold_case,
+            aliases: self.aliases.clone(),
+            functions: self.functions.clone(),
+            descriptions: self.descriptions.clone()
+        })
+    }
+
+}
+
+impl Categoriser {
+
+    /// Gets the category of a function.
+    pub fn get_category(&self, func: &ScarFunction) -> String {
+        let category = self.functions.get(&func.name)
+            .or_else(|| self.rules.iter()
+                .find(|(prefix, pattern, _)| {
+                    prefix.as_ref().is_none_or(|x| func.name.starts_with(x.as_str()))
+                        && pattern.as_ref().is_none_or(|x| x.is_match(&func.name))
+                })
+                .map(|x| &x.2))
+            .cloned()
+            .unwrap_or_else(|| get_default_category(func));
+        match self.find(&self.aliases, &category) {
+            Some((_, alias)) => alias.clone(),
+            None => category
+        }
+    }
+
+    /// Gets the key categories are grouped by, so names only differing in case end up in one category when folding case.
+    pub fn get_key(&self, category: &str) -> String {
+        match self.fold_case {
+            true => category.to_lowercase(),
+            false => category.to_string()
+        }
+    }
+
+    /// Gets the name a new category is listed under, preferring the spelling used by the rules when folding case.
+    pub fn get_name(&self, category: &str) -> String {
+        if !self.fold_case {
+            return category.to_string();
+        }
+        self.descriptions.keys()
+            .chain(self.aliases.values())
+            .chain(self.rules.iter().map(|x| &x.2))
+            .find(|x| x.eq_ignore_ascii_case(category))
+            .cloned()
+            .unwrap_or_else(|| category.to_string())
+    }
+
+    pub fn get_description(&self, category: &str) -> Option<String> {
+        self.find(&self.descriptions, category).map(|x| x.1.clone())
+    }
+
+    fn find<'a>(&self, map: &'a BTreeMap<String, String>, category: &str) -> Option<(&'a String, &'a String)> {
+        match self.fold_case {
+            true => map.iter().find(|x| x.0.eq_ignore_ascii_case(category)),
+            false => map.get_key_value(category)
+        }
+    }
+
+}
+
+/// Gets the prefix before the first `_`, else before the first `:`, else the first group of a function.
+fn get_default_category(func: &ScarFunction) -> String {
+    match func.name.find('_') {
+        Some(idx) => (func.name[..idx]).to_string(),
+        None => match func.name.find(':') {
+            Some(idx) => (func.name[..idx]).to_string(),
+            None => if !func.groups.is_empty() {
+                func.groups.first().unwrap_or(&func.name).to_string()
+            } else {
+                String::from("Other")
+            }
+        }
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn can_categorise_with_rules() {
+        let rules: super::CategoryRules = toml::from_str(r#"
+            fold_case = true
+
+            [[rules]]
+            prefix = "Squad_"
+            category = "Squad"
+
+            [[rules]]
+            pattern = "^(SGroup|EGroup)_"
+            category = "Group"
+
+            [aliases]
+            Misc = "Util"
+
+            [functions]
+            Util_SpawnGroup = "Group"
+
+            [descriptions]
+            Util = "Utility functions"
+        "#).unwrap();
+        let categoriser = rules.get_categoriser().unwrap();
+        let category = |name: &str| {
+            let func: crate::scarfile::ScarFunction = serde_json::from_value(serde_json::json!({ "name": name })).unwrap();
+            categoriser.get_category(&func)
+        };
+        assert_eq!(category("Squad_Kill"), "Squad");
+        assert_eq!(category("SGroup_Kill"), "Group");
+        assert_eq!(category("Util_SpawnGroup"), "Group");
+        assert_eq!(category("Misc_Thing"), "Util");
+        assert_eq!(category("MISC_Thing"), "Util");
+        assert_eq!(category("Player:GetTeam"), "Player");
+        assert_eq!(category("Noprefix"), "Other");
+        assert_eq!(categoriser.get_key("UTIL"), categoriser.get_key("Util"));
+        assert_eq!(categoriser.get_name("UTIL"), "Util");
+        assert_eq!(categoriser.get_description("util").as_deref(), Some("Utility functions"));
+
+        let invalid: super::CategoryRules = toml::from_str("[[rules]]\npattern = \"(\"\ncategory = \"Broken\"").unwrap();
+        assert!(matches!(invalid.get_categoriser(), Err(crate::scarerror::ScarDocError::InvalidRule { .. })));
+    }
+
+}
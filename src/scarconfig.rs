@@ -0,0 +1,196 @@
+use std::{fs, path::{Path, PathBuf}};
+use serde::Deserialize;
+
+use crate::scardoc::{ScarDoc, SortOrder, GenerateOptions, get_filtered_scar_files, get_scardoc_from_sources, read_scar_files_with_options, load_scardoc_from_json, save_scardoc_to_json};
+use crate::scardocmerger::{MergePolicy, merge_scardocs_with};
+use crate::scarerror::ScarDocError;
+use crate::scarfilter::ScarFileFilter;
+use crate::scarcategory::CategoryRules;
+use crate::{scardump::read_scardump, scarluals::export_luals, scarschema::validate_scardoc_file, scarsnippets::export_snippets};
+
+/// The project configuration file looked for in the working directory.
+pub const SCARDOC_CONFIG_FILE: &str = "scardoc.toml";
+
+/// The scardoc pipeline of a project, read from a `scardoc.toml` file.
+///
+/// Relative paths are relative to the directory of the configuration file.
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScarDocConfig {
+    /// Directories scar files are read from.
+    pub sources: Vec<PathBuf>,
+    /// Patterns of the files in the source directories to read, see [`ScarFileFilter`].
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub extensions: Vec<String>,
+    /// Scardoc dumps of the game, merged on top of the scar sources.
+    pub dumps: Vec<PathBuf>,
+    /// Manually written scardocs, merged on top of the scar sources and dumps.
+    pub overrides: Vec<PathBuf>,
+    pub merge: MergePolicy,
+    pub order: SortOrder,
+    pub jobs: usize,
+    pub cache: Option<PathBuf>,
+    /// The file the scardoc is saved to.
+    pub output: PathBuf,
+    pub categories: CategoryRules,
+    pub exporters: ScarDocExporters
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScarDocExporters {
+    /// The directory Lua Language Server definitions are exported to.
+    pub luals: Option<PathBuf>,
+    /// The file VS Code snippets are exported to.
+    pub snippets: Option<PathBuf>
+}
+
+impl Default for ScarDocConfig {
+    fn default() -> Self {
+        ScarDocConfig {
+            sources: Vec::new(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            extensions: Vec::new(),
+            dumps: Vec::new(),
+            overrides: Vec::new(),
+            merge: MergePolicy::default(),
+            order: SortOrder::default(),
+            jobs: 0,
+            cache: Some(PathBuf::from("scardoc.cache.json")),
+            output: PathBuf::from("scardoc.json"),
+            categories: CategoryRules::default(),
+            exporters: ScarDocExporters::default()
+        }
+    }
+}
+
+impl ScarDocConfig {
+
+    /// Loads a configuration file, resolving its paths relative to the directory it's in.
+    pub fn load<P: AsRef<Path>>(config_file: P) -> Result<ScarDocConfig, ScarDocError> {
+        let path = config_file.as_ref().display().to_string();
+        let content = fs::read_to_string(config_file.as_ref()).map_err(|e| ScarDocError::io(&path, e))?;
+        let mut config: ScarDocConfig = toml::from_str(&content).map_err(|e| ScarDocError::Config { path, source: e })?;
+        if let Some(dir) = config_file.as_ref().parent().filter(|x| !x.as_os_str().is_empty()) {
+            config.resolve_paths(dir);
+        }
+        Ok(config)
+    }
+
+    fn resolve_paths(&mut self, dir: &Path) {
+        let resolve = |x: &mut PathBuf| *x = dir.join(&*x);
+        self.sources.iter_mut().for_each(resolve);
+        self.dumps.iter_mut().for_each(resolve);
+        self.overrides.iter_mut().for_each(resolve);
+        self.cache.iter_mut().for_each(resolve);
+        resolve(&mut self.output);
+        self.exporters.luals.iter_mut().for_each(resolve);
+        self.exporters.snippets.iter_mut().for_each(resolve);
+    }
+
+    pub fn get_generate_options(&self) -> GenerateOptions {
+        GenerateOptions {
+            order: self.order,
+            jobs: self.jobs,
+            cache: self.cache.clone(),
+            filter: self.get_filter(),
+            categories: self.categories.clone()
+        }
+    }
+
+    fn get_filter(&self) -> ScarFileFilter {
+        ScarFileFilter { include: self.include.clone(), exclude: self.exclude.clone(), extensions: self.extensions.clone() }
+    }
+
+    /// Gets the scar files of every source directory that pass the filters.
+    pub fn get_scar_files(&self) -> Result<Vec<String>, ScarDocError> {
+        let filter = self.get_filter();
+        let mut files = Vec::new();
+        for source in &self.sources {
+            files.extend(get_filtered_scar_files(source, &filter)?);
+        }
+        Ok(files)
+    }
+
+}
+
+/// Generates the scardoc of every source directory, merges it with the dumps and overrides and saves it along with
+/// the configured exports.
+///
+/// The category rules apply to every function, including those of the dumps and overrides.
+pub fn build_scardoc(config: &ScarDocConfig) -> Result<ScarDoc, ScarDocError> {
+    let options = config.get_generate_options();
+    let categoriser = config.categories.get_categoriser()?;
+
+    // Sources are read together so they share one cache
+    let files = config.get_scar_files()?;
+    let mut docs = vec![get_scardoc_from_sources(read_scar_files_with_options(&files, &options), SortOrder::Source, &categoriser)];
+
+    for dump in &config.dumps {
+        docs.push(read_scardump(dump.display().to_string())?);
+    }
+    for file in &config.overrides {
+        let path = file.display().to_string();
+        let errors = validate_scardoc_file(file)?;
+        if !errors.is_empty() {
+            return Err(ScarDocError::Invalid { path, errors });
+        }
+        docs.push(load_scardoc_from_json(path)?);
+    }
+
+    let mut doc = merge_scardocs_with(&docs, config.merge, &categoriser)?;
+    doc.sort(config.order);
+
+    save_scardoc_to_json(&doc, &config.output.display().to_string())?;
+    if let Some(out_dir) = &config.exporters.luals {
+        export_luals(&doc, out_dir)?;
+    }
+    if let Some(out_file) = &config.exporters.snippets {
+        export_snippets(&doc, out_file)?;
+    }
+    Ok(doc)
+}
+
+mod tests {
+
+    #[test]
+    fn can_read_config() {
+        let config: super::ScarDocConfig = toml::from_str(r#"
+            sources = ["scar"]
+            exclude = ["tests/"]
+            dumps = ["scar/dump.txt"]
+            merge = "keep-first"
+            order = "source"
+
+            [exporters]
+            snippets = "scar.code-snippets"
+        "#).unwrap();
+        assert_eq!(config.sources, vec![std::path::PathBuf::from("scar")]);
+        assert_eq!(config.merge, crate::scardocmerger::MergePolicy::KeepFirst);
+        assert_eq!(config.order, crate::scardoc::SortOrder::Source);
+        assert_eq!(config.output, std::path::PathBuf::from("scardoc.json"));
+        assert!(config.exporters.luals.is_none());
+        assert!(toml::from_str::<super::ScarDocConfig>("source = [\"scar\"]").is_err());
+    }
+
+    #[test]
+    fn can_build_scardoc() {
+        let dir = std::env::temp_dir().join(format!("scardoc-config-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = super::ScarDocConfig {
+            sources: vec!["scar".into()],
+            dumps: vec!["scar/dump.txt".into()],
+            cache: None,
+            output: dir.join("scardoc.json"),
+            ..Default::default()
+        };
+        let doc = super::build_scardoc(&config).unwrap();
+        assert!(doc.categories.iter().any(|x| x.category_functions.iter().any(|y| y.name == "Util_SpawnGroup")));
+        assert!(!doc.enums.is_empty());
+        assert!(dir.join("scardoc.json").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+}
@@ -1,23 +1,100 @@
-use walkdir::{DirEntry, WalkDir};
-use std::{path::Path, collections::HashMap};
+use walkdir::WalkDir;
+use std::{path::{Path, PathBuf}, collections::HashMap, fs::File, io::{self, BufReader, Write, IsTerminal}, thread, sync::atomic::{AtomicUsize, Ordering}};
 use serde::{Serialize, Deserialize};
 
 use crate::scarfile::*;
 use crate::scarenum::*;
+use crate::scartype::{ScarType, get_builtin_types};
+use crate::scarmigration::migrate_scardoc;
+use crate::scarerror::ScarDocError;
+use crate::scarcache::{ScarCache, read_scar_files_cached};
+use crate::scarfilter::ScarFileFilter;
+use crate::scarcategory::{CategoryRules, Categoriser};
 
-#[derive(Serialize, Deserialize)]
+/// The version of the scardoc format written by this generator.
+///
+/// Must be bumped whenever the format changes, even if fields are only added, so older tools refuse newer scardoc
+/// files instead of rejecting or dropping what they don't know. Every bump adds a migration (see `scarmigration`).
+pub const SCARDOC_FORMAT_VERSION: u32 = 8;
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ScarDoc {
+    #[serde(default)]
+    pub format_version: u32,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub categories: Vec<ScarDocCategory>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub enums: Vec<ScarEnum>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub globals: Vec<ScarGlobal>
+    pub globals: Vec<ScarGlobal>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub types: Vec<ScarType>
+}
+
+/// The order categories, functions, enums and globals are listed in.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// Sorted by name.
+    #[default]
+    Name,
+    /// The order they were declared in, with files read in alphabetical order.
+    Source
+}
+
+/// Options for generating a scardoc from a scar directory.
+#[derive(Clone, Default)]
+pub struct GenerateOptions {
+    pub order: SortOrder,
+    /// The amount of threads used to parse scar files, using every available core if 0.
+    pub jobs: usize,
+    /// A file caching the parsed scar files, so only the files that changed since the last run are reparsed.
+    pub cache: Option<PathBuf>,
+    /// Which files in the scar directory are read.
+    pub filter: ScarFileFilter,
+    /// Which category each function is listed in.
+    pub categories: CategoryRules
+}
+
+impl ScarDoc {
+    /// Sorts the scardoc in the given order.
+    ///
+    /// Scardocs are always built in source order, so sorting in source order leaves the scardoc as is.
+    pub fn sort(&mut self, order: SortOrder) {
+        if order == SortOrder::Source {
+            return;
+        }
+        self.categories.sort_by(|a, b| a.category_name.cmp(&b.category_name));
+        for category in self.categories.iter_mut() {
+            category.category_functions.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+        self.enums.sort_by(|a, b| a.name.cmp(&b.name));
+        self.globals.sort_by(|a, b| a.name.cmp(&b.name));
+        self.types.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    /// Gets the function named `name`, whatever category it's in.
+    pub fn get_function(&self, name: &str) -> Option<&ScarFunction> {
+        self.categories.iter().flat_map(|x| x.category_functions.iter()).find(|x| x.name == name)
+    }
+
+    /// Gets the function named `name` to change it, whatever category it's in.
+    pub fn get_function_mut(&mut self, name: &str) -> Option<&mut ScarFunction> {
+        self.categories.iter_mut().flat_map(|x| x.category_functions.iter_mut()).find(|x| x.name == name)
+    }
 }
 
-#[derive(Serialize, Deserialize)]
+impl Default for ScarDoc {
+    fn default() -> Self {
+        ScarDoc { format_version: SCARDOC_FORMAT_VERSION, categories: Vec::new(), enums: Vec::new(), globals: Vec::new(), types: Vec::new() }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ScarDocCategory {
     pub category_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category_description: Option<String>,
     pub category_functions: Vec<ScarFunction>
 }
 
@@ -29,77 +106,243 @@ pub struct ScarGlobal {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub global_type: Option<String>
+    pub global_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<ScarDeprecation>,
+    /// The game version the global was introduced in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>
+}
+
+pub fn generate_scardoc<P: AsRef<Path>>(dir_path: P) -> Result<ScarDoc, ScarDocError> {
+    generate_scardoc_with_options(dir_path, &GenerateOptions::default())
 }
 
-pub fn generate_scardoc<P: AsRef<Path>>(dir_path: P) -> Result<ScarDoc, &'static str> {
+pub fn generate_scardoc_with_options<P: AsRef<Path>>(dir_path: P, options: &GenerateOptions) -> Result<ScarDoc, ScarDocError> {
+    let categoriser = options.categories.get_categoriser()?;
+    let files = get_filtered_scar_files(dir_path, &options.filter)?;
+    let sources = read_scar_files_with_options(&files, options);
+    Ok(get_scardoc_from_sources(sources, options.order, &categoriser))
+}
+
+/// Parses scar files using the threads and cache given in `options`.
+pub(crate) fn read_scar_files_with_options(files: &[String], options: &GenerateOptions) -> Vec<Result<ScarSourceFile, ScarDocError>> {
+    match &options.cache {
+        None => read_scar_files(files, options.jobs),
+        Some(cache_path) => {
+            let mut cache = ScarCache::load(cache_path);
+            let sources = read_scar_files_cached(files, options.jobs, &mut cache);
+            // A cache that can't be saved only costs time on the next run
+            if let Err(e) = cache.save(cache_path) {
+                eprintln!("Failed saving scar cache: {}", e);
+            }
+            sources
+        }
+    }
+}
+
+/// Builds a scardoc from read scar files, printing the files that failed to read and any warnings.
+///
+/// The scardoc documents the built-in types along with the types, globals and enums defined by the scar files.
+pub(crate) fn get_scardoc_from_sources(sources: Vec<Result<ScarSourceFile, ScarDocError>>, order: SortOrder, categoriser: &Categoriser) -> ScarDoc {
     let mut results = Vec::new();
+    let mut types = get_builtin_types();
+    let mut type_map: HashMap<String, usize> = types.iter().enumerate().map(|(i, x)| (x.name.clone(), i)).collect();
+    let mut globals: Vec<ScarGlobal> = Vec::new();
+    let mut global_map: HashMap<String, usize> = HashMap::new();
+    let mut enums: Vec<ScarEnum> = Vec::new();
+    let mut enum_map: HashMap<String, usize> = HashMap::new();
 
-    for entry in WalkDir::new(dir_path) {
-        let entry = entry.map_err(|_| "Failed to access directory entry")?;
-        if is_scar_file(&entry) {
-            let file_path = entry.path().to_str().unwrap().to_string();
-            print!("Reading scar file: {}", file_path);
-            match get_scar_sourcefile(file_path) {
-                Err(e) => {
-                    println!(" ... failed");
-                    eprintln!("\tFailed reading scar file with error: {}", e)
-                },
-                Ok(src) => {
-                    println!(" ... ok");
-                    if src.functions.len() > 0 {
-                        results.push(src)
+    for result in sources {
+        match result {
+            Err(e) => eprintln!("Failed reading scar file with error: {}", e),
+            Ok(src) => {
+                for warning in &src.warnings {
+                    eprintln!("{}", warning);
+                }
+                for ty in src.types.iter() {
+                    match type_map.get(&ty.name) {
+                        Some(&idx) => {
+                            if !types[idx].builtin {
+                                eprintln!("{}: type {} is defined more than once", src.source_name, ty.name);
+                            }
+                            types[idx] = ty.clone();
+                        }
+                        None => {
+                            type_map.insert(ty.name.clone(), types.len());
+                            types.push(ty.clone());
+                        }
+                    }
+                }
+                for global in src.globals.iter() {
+                    match global_map.get(&global.name) {
+                        Some(&idx) => {
+                            eprintln!("{}: global {} is defined more than once", src.source_name, global.name);
+                            globals[idx] = global.clone();
+                        }
+                        None => {
+                            global_map.insert(global.name.clone(), globals.len());
+                            globals.push(global.clone());
+                        }
                     }
                 }
+                for scar_enum in src.enums.iter() {
+                    match enum_map.get(&scar_enum.name) {
+                        Some(&idx) => {
+                            eprintln!("{}: enum {} is defined more than once", src.source_name, scar_enum.name);
+                            enums[idx] = scar_enum.clone();
+                        }
+                        None => {
+                            enum_map.insert(scar_enum.name.clone(), enums.len());
+                            enums.push(scar_enum.clone());
+                        }
+                    }
+                }
+                if !src.functions.is_empty() {
+                    results.push(src)
+                }
             }
         }
     }
 
-    let categorised = categorise_functions(results)
+    let categorised = categorise_functions_with(results, categoriser)
         .into_iter()
         .filter(|x| !x.category_functions.is_empty())
         .collect();
 
-    Ok(ScarDoc { categories: categorised, enums: Vec::new(), globals: Vec::new() })
-    
+    let mut doc = ScarDoc { format_version: SCARDOC_FORMAT_VERSION, categories: categorised, enums, globals, types };
+    doc.sort(order);
+    doc
+
 }
 
-fn is_scar_file(entry: &DirEntry) -> bool {
-    entry.file_type().is_file() && entry.path().extension() == Some("scar".as_ref())
+/// Gets the path of every scar file in `dir_path` not skipped by its `.scardocignore` file, in alphabetical order.
+pub fn get_scar_files<P: AsRef<Path>>(dir_path: P) -> Result<Vec<String>, ScarDocError> {
+    get_filtered_scar_files(dir_path, &ScarFileFilter::default())
 }
 
-pub fn categorise_functions(sources: Vec<ScarSourceFile>) -> Vec<ScarDocCategory> {
+/// Gets the path of every file in `dir_path` selected by `filter`, in alphabetical order.
+pub fn get_filtered_scar_files<P: AsRef<Path>>(dir_path: P, filter: &ScarFileFilter) -> Result<Vec<String>, ScarDocError> {
+    let root = dir_path.as_ref();
+    let matcher = filter.get_matcher(root)?;
+    let get_relative_path = |x: &Path| x.strip_prefix(root).unwrap_or(x).to_path_buf();
 
-    let mut map: HashMap<String, ScarDocCategory> = HashMap::new();
+    // Excluded directories are skipped rather than walked
+    let walker = WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|x| x.depth() == 0 || !x.file_type().is_dir() || !matcher.is_excluded_dir(&get_relative_path(x.path())));
 
-    for source in sources {
-        for func in source.functions {
-            let func_category = categorise_function(&func);
-            map.entry(func_category.clone())
-                .or_insert_with(|| ScarDocCategory {
-                    category_name: func_category.clone(),
-                    category_functions: Vec::new(),
-                })
-                .category_functions.push(func);
+    let mut files = Vec::new();
+    for entry in walker {
+        let entry = entry.map_err(|e| {
+            let path = e.path().unwrap_or(root).display().to_string();
+            // Symlink loops are the only walk errors without an underlying io error
+            let source = if e.io_error().is_some() { e.into_io_error().unwrap() } else { io::Error::other(e) };
+            ScarDocError::io(path, source)
+        })?;
+        if entry.file_type().is_file() && matcher.is_match(&get_relative_path(entry.path())) {
+            files.push(entry.path().to_str().unwrap().to_string());
         }
     }
+    Ok(files)
+}
 
-    map.into_values().collect()
-
+/// Parses scar files on `jobs` threads (every available core if 0), returning the results in the same order as `files`.
+///
+/// Progress is reported on stderr when it's a terminal.
+pub fn read_scar_files(files: &[String], jobs: usize) -> Vec<Result<ScarSourceFile, ScarDocError>> {
+    read_scar_files_with(files, jobs, |file| get_scar_sourcefile(file.to_string()))
 }
 
-fn categorise_function(func: &ScarFunction) -> String {
-    match func.name.find('_') {
-        Some(idx) => (func.name[..idx]).to_string(),
-        None => match func.name.find(':') {
-            Some(idx) => (func.name[..idx]).to_string(),
-            None => if func.groups.len() > 0 {
-                func.groups.get(0).unwrap_or(&func.name).to_string()
-            } else {
-                String::from("Other")
+/// Calls `read` on every file on `jobs` threads, like [`read_scar_files`], returning the results in the same order as `files`.
+pub(crate) fn read_scar_files_with<T, F>(files: &[String], jobs: usize, read: F) -> Vec<T>
+    where T: Send, F: Fn(&str) -> T + Sync {
+    let jobs = match jobs {
+        0 => thread::available_parallelism().map_or(1, |x| x.get()),
+        n => n
+    }.clamp(1, files.len().max(1));
+
+    let show_progress = io::stderr().is_terminal();
+    let next = AtomicUsize::new(0);
+    let done = AtomicUsize::new(0);
+
+    let mut results: Vec<(usize, T)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs).map(|_| scope.spawn(|| {
+            let mut parsed = Vec::new();
+            loop {
+                let idx = next.fetch_add(1, Ordering::Relaxed);
+                if idx >= files.len() {
+                    break;
+                }
+                parsed.push((idx, read(&files[idx])));
+                let count = done.fetch_add(1, Ordering::Relaxed) + 1;
+                if show_progress {
+                    eprint!("\rParsing scar files: {}/{}", count, files.len());
+                }
             }
+            parsed
+        })).collect();
+        workers.into_iter().flat_map(|x| x.join().expect("scar parser thread panicked")).collect()
+    });
+
+    if show_progress && !files.is_empty() {
+        eprintln!();
+    }
+
+    results.sort_by_key(|x| x.0);
+    results.into_iter().map(|x| x.1).collect()
+}
+
+pub fn load_scardoc_from_json(file_path: String) -> Result<ScarDoc, ScarDocError> {
+
+    let file = File::open(&file_path).map_err(|e| ScarDocError::io(&file_path, e))?;
+    let reader = BufReader::new(file);
+
+    // Older scardocs are migrated to the current format before being read
+    let doc: serde_json::Value = serde_json::from_reader(reader).map_err(|e| ScarDocError::json(&file_path, e))?;
+    let doc = migrate_scardoc(doc, &file_path)?;
+
+    serde_json::from_value(doc).map_err(|e| ScarDocError::json(&file_path, e))
+}
+
+pub fn save_scardoc_to_json(doc: &ScarDoc, filepath: &str) -> Result<(), ScarDocError> {
+    let json = serde_json::to_string_pretty(doc).map_err(|e| ScarDocError::json(filepath, e))?;
+    let mut file = File::create(filepath).map_err(|e| ScarDocError::io(filepath, e))?;
+    file.write_all(json.as_bytes()).map_err(|e| ScarDocError::io(filepath, e))
+}
+
+/// Lists the functions of scar files by category, using the default category rules.
+pub fn categorise_functions(sources: Vec<ScarSourceFile>) -> Vec<ScarDocCategory> {
+    categorise_functions_with(sources, &Categoriser::default())
+}
+
+/// Lists the functions of scar files in the category given by `categoriser`.
+pub fn categorise_functions_with(sources: Vec<ScarSourceFile>, categoriser: &Categoriser) -> Vec<ScarDocCategory> {
+
+    // Categories are kept in the order they're first seen in
+    let mut categories: Vec<ScarDocCategory> = Vec::new();
+    let mut map: HashMap<String, usize> = HashMap::new();
+
+    for source in sources {
+        for func in source.functions {
+            let func_category = categoriser.get_category(&func);
+            let idx = *map.entry(categoriser.get_key(&func_category))
+                .or_insert_with(|| {
+                    let category_name = categoriser.get_name(&func_category);
+                    categories.push(ScarDocCategory {
+                        category_description: categoriser.get_description(&category_name),
+                        category_name,
+                        category_functions: Vec::new(),
+                    });
+                    categories.len() - 1
+                });
+            categories[idx].category_functions.push(func);
         }
     }
+
+    categories
+
 }
 
 mod tests {
@@ -107,9 +350,36 @@ mod tests {
     #[test]
     fn can_get_scardoc() {
         let result = super::generate_scardoc("scar");
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
         let scardoc = result.unwrap();
-        assert_eq!(scardoc.categories.len() > 0, true)
+        assert!(!scardoc.categories.is_empty())
+    }
+
+    #[test]
+    fn can_sort_scardoc() {
+        let source = super::generate_scardoc_with_options("scar", &super::GenerateOptions { order: super::SortOrder::Source, ..Default::default() }).unwrap();
+        let sorted = super::generate_scardoc("scar").unwrap();
+        let names: Vec<&str> = sorted.categories.iter().map(|x| x.category_name.as_str()).collect();
+        let mut expected = names.clone();
+        expected.sort();
+        assert_eq!(names, expected);
+        // Files are read alphabetically, so emmylua.scar is read before simple.scar
+        let first = &source.categories.first().unwrap().category_functions;
+        assert_eq!(first.first().unwrap().name, "Util_SpawnGroup");
+        assert_eq!(first.get(1).unwrap().name, "Util_ScarPos");
+        let sorted_first = &sorted.categories.first().unwrap().category_functions;
+        assert_eq!(sorted_first.first().unwrap().name, "Util_ScarPos");
+    }
+
+    #[test]
+    fn can_read_scar_files_in_parallel() {
+        let files = super::get_scar_files("scar").unwrap();
+        let files: Vec<String> = files.iter().cycle().take(files.len() * 8).cloned().collect();
+        let results = super::read_scar_files(&files, 4);
+        assert_eq!(results.len(), files.len());
+        for (file, result) in files.iter().zip(results) {
+            assert_eq!(&result.unwrap().source_name, file);
+        }
     }
 
 }
@@ -1,39 +1,131 @@
 use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
 
-use crate::{scardoc::{ScarDoc, categorise_functions, ScarDocCategory, ScarGlobal}, scarfile::{ScarFunction, ScarSourceFile, ScarParameter}, scarenum::{ScarEnum, ScarEnumValue}};
+use crate::{scarerror::ScarDocError, scardoc::{ScarDoc, SCARDOC_FORMAT_VERSION, categorise_functions_with, ScarDocCategory, ScarGlobal}, scarfile::{ScarFunction, ScarSourceFile, ScarParameter}, scarenum::{ScarEnum, ScarEnumValue}, scartype::ScarType};
+use crate::scarcategory::Categoriser;
 
-pub fn merge_scardoc(main: &ScarDoc, second: &ScarDoc) -> ScarDoc {
-    let categories = merge_scardoc_functions(main, second);
-    let enums = merge_scardoc_enums(main, second);
-    let globals = merge_scardoc_globals(main, second);
-    ScarDoc { categories, enums, globals }
+/// How functions, enums and globals defined in both scardocs are merged.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum MergePolicy {
+    /// The later scardoc overrides what it documents.
+    #[default]
+    Override,
+    /// The first definition is kept, later scardocs only introduce new definitions.
+    KeepFirst,
+    /// Definitions that differ between scardocs are a merge conflict.
+    Strict
 }
 
-fn merge_scardoc_functions(main: &ScarDoc, second: &ScarDoc) -> Vec<ScarDocCategory> {
-    let mut funcs = HashMap::new();
+/// Merges `second` into `main`, with `second` taking precedence.
+///
+/// The result is in source order: everything from `main` keeps its order, followed by what `second` introduces.
+pub fn merge_scardoc(main: &ScarDoc, second: &ScarDoc) -> Result<ScarDoc, ScarDocError> {
+    merge_scardoc_with_policy(main, second, MergePolicy::Override)
+}
+
+/// Merges `second` into `main`, resolving definitions found in both using `policy`.
+pub fn merge_scardoc_with_policy(main: &ScarDoc, second: &ScarDoc, policy: MergePolicy) -> Result<ScarDoc, ScarDocError> {
+    merge_scardoc_with(main, second, policy, &Categoriser::default())
+}
+
+/// Merges `second` into `main`, resolving definitions found in both using `policy` and listing the functions in the
+/// category given by `categoriser`.
+pub fn merge_scardoc_with(main: &ScarDoc, second: &ScarDoc, policy: MergePolicy, categoriser: &Categoriser) -> Result<ScarDoc, ScarDocError> {
+    // Scardocs must be migrated (by loading them with 'load_scardoc_from_json') before they can be merged
+    for doc in [main, second] {
+        if doc.format_version != SCARDOC_FORMAT_VERSION {
+            return Err(ScarDocError::FormatVersionMismatch { version: doc.format_version });
+        }
+    }
+    let categories = merge_scardoc_functions(main, second, policy, categoriser)?;
+    let enums = merge_scardoc_enums(main, second, policy)?;
+    let globals = merge_scardoc_globals(main, second, policy)?;
+    let types = merge_scardoc_types(main, second, policy)?;
+    Ok(ScarDoc { format_version: SCARDOC_FORMAT_VERSION, categories, enums, globals, types })
+}
+
+/// Merges any amount of scardocs, with each scardoc taking precedence over the ones before it.
+pub fn merge_scardocs(docs: &[ScarDoc]) -> Result<ScarDoc, ScarDocError> {
+    merge_scardocs_with_policy(docs, MergePolicy::Override)
+}
+
+/// Merges any amount of scardocs in order, resolving definitions found in several of them using `policy`.
+pub fn merge_scardocs_with_policy(docs: &[ScarDoc], policy: MergePolicy) -> Result<ScarDoc, ScarDocError> {
+    merge_scardocs_with(docs, policy, &Categoriser::default())
+}
+
+/// Merges any amount of scardocs in order, resolving definitions found in several of them using `policy` and listing
+/// the functions in the category given by `categoriser`.
+pub fn merge_scardocs_with(docs: &[ScarDoc], policy: MergePolicy, categoriser: &Categoriser) -> Result<ScarDoc, ScarDocError> {
+    let Some((first, rest)) = docs.split_first() else {
+        return Ok(ScarDoc::default());
+    };
+    let mut result = first.clone();
+    for doc in rest {
+        result = merge_scardoc_with(&result, doc, policy, categoriser)?;
+    }
+    Ok(result)
+}
+
+fn merge_scardoc_functions(main: &ScarDoc, second: &ScarDoc, policy: MergePolicy, categoriser: &Categoriser) -> Result<Vec<ScarDocCategory>, ScarDocError> {
+    // Functions of the main scardoc keep their order, with new functions added after them
+    let mut funcs: Vec<ScarFunction> = Vec::new();
+    let mut map = HashMap::new();
     for category in main.categories.as_slice() {
         for func in category.category_functions.as_slice() {
-            funcs.insert(func.name.clone(), func.clone());
+            match map.get(&func.name) {
+                Some(&idx) => funcs[idx] = func.clone(),
+                None => {
+                    map.insert(func.name.clone(), funcs.len());
+                    funcs.push(func.clone());
+                }
+            }
         }
     }
     for category in second.categories.as_slice() {
         for func in category.category_functions.as_slice() {
-            funcs.entry(func.name.clone())
-            .or_insert_with(|| { println!("Introducing function {}",  func.name.clone()); func.clone() })
-            .merge_with(&func);
+            match map.get(&func.name) {
+                None => {
+                    println!("Introducing function {}", func.name);
+                    map.insert(func.name.clone(), funcs.len());
+                    funcs.push(func.clone());
+                }
+                Some(&idx) => match policy {
+                    MergePolicy::Override => funcs[idx].merge_with(func),
+                    MergePolicy::KeepFirst => {},
+                    MergePolicy::Strict => if !funcs[idx].eq(func) {
+                        return Err(ScarDocError::MergeConflict { message: format!("function {} is documented differently", func.name) });
+                    }
+                }
+            }
         }
     }
     let temp_source_file = vec![ScarSourceFile{
         source_name: String::from("temp"),
-        functions: funcs.into_values().collect()
+        functions: funcs,
+        types: Vec::new(),
+        globals: Vec::new(),
+        enums: Vec::new(),
+        warnings: Vec::new()
     }];
-    categorise_functions(temp_source_file)
+    let mut categories = categorise_functions_with(temp_source_file, categoriser);
+
+    // Descriptions given by the categoriser take precedence, then those of the second scardoc
+    for category in categories.iter_mut().filter(|x| x.category_description.is_none()) {
+        let key = categoriser.get_key(&category.category_name);
+        category.category_description = [second, main].iter()
+            .flat_map(|x| x.categories.iter())
+            .find(|x| categoriser.get_key(&x.category_name) == key && x.category_description.is_some())
+            .and_then(|x| x.category_description.clone());
+    }
+    Ok(categories)
 }
 
 impl PartialEq for ScarParameter {
     fn eq(&self, other: &Self) -> bool {
         self.arg_name == other.arg_name && self.arg_type == other.arg_type && self.arg_description == other.arg_description
-        && self.arg_required == other.arg_required
+        && self.arg_required == other.arg_required && self.arg_default == other.arg_default
     }
 }
 
@@ -43,7 +135,10 @@ impl ScarFunction {
         && self.description_short == other.description_short && self.example == other.example
         && self.groups == other.groups && self.return_description == other.return_description
         && self.return_type == other.return_type && self.source_file == other.source_file
+        && self.source_line == other.source_line
         && self.parameters == other.parameters
+        && self.deprecated == other.deprecated && self.since == other.since
+        && self.see == other.see
     }
     pub fn merge_with(&mut self, other: &Self) {
         if self.eq(other) {
@@ -78,6 +173,10 @@ impl ScarFunction {
             (None, Some(b)) => Some(b.clone()),
             _ => self.source_file.clone()
         };
+        // The line only makes sense together with the file it belongs to
+        if other.source_file.is_some() {
+            self.source_line = other.source_line
+        }
         self.description_extended = match (self.description_extended.len(), other.description_extended.len()) {
             (_, i) if i > 0 => other.description_extended.clone(),
             _ => self.description_extended.clone()
@@ -85,60 +184,167 @@ impl ScarFunction {
         self.parameters = match (self.parameters.len(), other.parameters.len()) {
             (_, i) if i > 0 => other.parameters.clone(),
             _ => self.parameters.clone()
-        }
+        };
+        self.deprecated = match (self.deprecated.clone(), other.deprecated.clone()) {
+            (Some(_), Some(b)) => Some(b.clone()),
+            (None, Some(b)) => Some(b.clone()),
+            _ => self.deprecated.clone()
+        };
+        self.since = match (self.since.clone(), other.since.clone()) {
+            (Some(_), Some(b)) => Some(b.clone()),
+            (None, Some(b)) => Some(b.clone()),
+            _ => self.since.clone()
+        };
+        self.see = match (self.see.len(), other.see.len()) {
+            (_, i) if i > 0 => other.see.clone(),
+            _ => self.see.clone()
+        };
     }
 }
 
-fn merge_scardoc_enums(main: &ScarDoc, second: &ScarDoc) -> Vec<ScarEnum> {
-    let mut enums = HashMap::new();
+fn merge_scardoc_enums(main: &ScarDoc, second: &ScarDoc, policy: MergePolicy) -> Result<Vec<ScarEnum>, ScarDocError> {
+    let mut enums: Vec<ScarEnum> = Vec::new();
+    let mut map = HashMap::new();
     for enum_def in main.enums.as_slice() {
-        enums.insert(enum_def.name.clone(), enum_def.clone());
+        match map.get(&enum_def.name) {
+            Some(&idx) => enums[idx] = enum_def.clone(),
+            None => {
+                map.insert(enum_def.name.clone(), enums.len());
+                enums.push(enum_def.clone());
+            }
+        }
     }
     for enum_def in second.enums.as_slice() {
-        enums.entry(enum_def.name.clone())
-        .or_insert_with(|| { println!("Introducing enum {}", enum_def.name.clone()); enum_def.clone() })
-        .merge_with(&enum_def);
+        match map.get(&enum_def.name) {
+            None => {
+                println!("Introducing enum {}", enum_def.name);
+                map.insert(enum_def.name.clone(), enums.len());
+                enums.push(enum_def.clone());
+            }
+            Some(&idx) => match policy {
+                MergePolicy::Override => enums[idx].merge_with(enum_def),
+                MergePolicy::KeepFirst => {},
+                MergePolicy::Strict => if !enums[idx].eq(enum_def) {
+                    return Err(ScarDocError::MergeConflict { message: format!("enum {} is documented differently", enum_def.name) });
+                }
+            }
+        }
+    }
+    Ok(enums)
+}
+
+impl PartialEq for ScarEnumValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.value == other.value && self.description == other.description
+        && self.deprecated == other.deprecated && self.source_file == other.source_file && self.source_line == other.source_line
+    }
+}
+
+impl ScarEnumValue {
+    pub fn merge_with(&mut self, other: &Self) {
+        self.value = match (self.value.clone(), other.value.clone()) {
+            (Some(_), Some(b)) => Some(b.clone()),
+            (None, Some(b)) => Some(b.clone()),
+            _ => self.value.clone()
+        };
+        self.description = match (self.description.clone(), other.description.clone()) {
+            (Some(_), Some(b)) => Some(b.clone()),
+            (None, Some(b)) => Some(b.clone()),
+            _ => self.description.clone()
+        };
+        self.deprecated = match (self.deprecated.clone(), other.deprecated.clone()) {
+            (Some(_), Some(b)) => Some(b.clone()),
+            (None, Some(b)) => Some(b.clone()),
+            _ => self.deprecated.clone()
+        };
+        self.source_file = match (self.source_file.clone(), other.source_file.clone()) {
+            (Some(_), Some(b)) => Some(b.clone()),
+            (None, Some(b)) => Some(b.clone()),
+            _ => self.source_file.clone()
+        };
+        // The line only makes sense together with the file it belongs to
+        if other.source_file.is_some() {
+            self.source_line = other.source_line
+        }
     }
-    enums.into_values().collect()
 }
 
 impl ScarEnum {
     pub fn has_value(&self, other:&ScarEnumValue) -> bool {
         self.values.as_slice()
-        .into_iter()
-        .any(|x| x.name == other.name && x.value == other.value)
+        .iter()
+        .any(|x| x.eq(other))
     }
     pub fn eq(&mut self, other: &Self) -> bool {
-        self.name == other.name && self.values.len() == other.values.len() && self.values.as_slice().into_iter().all(|x| other.has_value(&x))
+        self.name == other.name && self.values.len() == other.values.len() && self.values.as_slice().iter().all(|x| other.has_value(x))
+        && self.description == other.description && self.deprecated == other.deprecated && self.since == other.since && self.table == other.table
     }
     pub fn merge_with(&mut self, other: &Self) {
         if self.eq(other) {
             return;
         }
         println!("Merging enum {}", self.name);
-        self.values = match (self.values.len(), other.values.len()) {
-            (_, i) if i > 0 => other.values.clone(),
-            _ => self.values.clone()
+        // Values are merged one by one, so documenting a single value doesn't drop the others
+        for value in other.values.iter() {
+            match self.values.iter_mut().find(|x| x.name == value.name) {
+                Some(existing) => existing.merge_with(value),
+                None => self.values.push(value.clone())
+            }
         }
+        self.table = self.table || other.table;
+        self.description = match (self.description.clone(), other.description.clone()) {
+            (Some(_), Some(b)) => Some(b.clone()),
+            (None, Some(b)) => Some(b.clone()),
+            _ => self.description.clone()
+        };
+        self.deprecated = match (self.deprecated.clone(), other.deprecated.clone()) {
+            (Some(_), Some(b)) => Some(b.clone()),
+            (None, Some(b)) => Some(b.clone()),
+            _ => self.deprecated.clone()
+        };
+        self.since = match (self.since.clone(), other.since.clone()) {
+            (Some(_), Some(b)) => Some(b.clone()),
+            (None, Some(b)) => Some(b.clone()),
+            _ => self.since.clone()
+        };
     }
 }
 
-fn merge_scardoc_globals(main: &ScarDoc, second: &ScarDoc) -> Vec<ScarGlobal> {
-    let mut globals = HashMap::new();
+fn merge_scardoc_globals(main: &ScarDoc, second: &ScarDoc, policy: MergePolicy) -> Result<Vec<ScarGlobal>, ScarDocError> {
+    let mut globals: Vec<ScarGlobal> = Vec::new();
+    let mut map = HashMap::new();
     for global in main.globals.as_slice() {
-        globals.insert(global.name.clone(), global.clone());
+        match map.get(&global.name) {
+            Some(&idx) => globals[idx] = global.clone(),
+            None => {
+                map.insert(global.name.clone(), globals.len());
+                globals.push(global.clone());
+            }
+        }
     }
     for global in second.globals.as_slice() {
-        globals.entry(global.name.clone())
-        .or_insert_with(|| { println!("Introducing global {}", global.name.clone()); global.clone() })
-        .merge_with(&global);
+        match map.get(&global.name) {
+            None => {
+                println!("Introducing global {}", global.name);
+                map.insert(global.name.clone(), globals.len());
+                globals.push(global.clone());
+            }
+            Some(&idx) => match policy {
+                MergePolicy::Override => globals[idx].merge_with(global),
+                MergePolicy::KeepFirst => {},
+                MergePolicy::Strict => if !globals[idx].eq(global) {
+                    return Err(ScarDocError::MergeConflict { message: format!("global {} is documented differently", global.name) });
+                }
+            }
+        }
     }
-    globals.into_values().collect()
+    Ok(globals)
 }
 
 impl ScarGlobal {
     pub fn eq(&mut self, other: &Self) -> bool {
         self.name == other.name && self.description == other.description && self.global_type == other.global_type && self.value == other.value
+        && self.deprecated == other.deprecated && self.since == other.since
     }
     pub fn merge_with(&mut self, other: &Self) {
         if self.eq(other) {
@@ -160,5 +366,199 @@ impl ScarGlobal {
             (None, Some(b)) => Some(b.clone()),
             _ => self.value.clone()
         };
+        self.deprecated = match (self.deprecated.clone(), other.deprecated.clone()) {
+            (Some(_), Some(b)) => Some(b.clone()),
+            (None, Some(b)) => Some(b.clone()),
+            _ => self.deprecated.clone()
+        };
+        self.since = match (self.since.clone(), other.since.clone()) {
+            (Some(_), Some(b)) => Some(b.clone()),
+            (None, Some(b)) => Some(b.clone()),
+            _ => self.since.clone()
+        };
+    }
+}
+
+fn merge_scardoc_types(main: &ScarDoc, second: &ScarDoc, policy: MergePolicy) -> Result<Vec<ScarType>, ScarDocError> {
+    let mut types: Vec<ScarType> = Vec::new();
+    let mut map = HashMap::new();
+    for ty in main.types.as_slice() {
+        match map.get(&ty.name) {
+            Some(&idx) => types[idx] = ty.clone(),
+            None => {
+                map.insert(ty.name.clone(), types.len());
+                types.push(ty.clone());
+            }
+        }
+    }
+    for ty in second.types.as_slice() {
+        match map.get(&ty.name) {
+            None => {
+                println!("Introducing type {}", ty.name);
+                map.insert(ty.name.clone(), types.len());
+                types.push(ty.clone());
+            }
+            Some(&idx) => match policy {
+                MergePolicy::Override => types[idx].merge_with(ty),
+                MergePolicy::KeepFirst => {},
+                MergePolicy::Strict => if !types[idx].eq(ty) {
+                    return Err(ScarDocError::MergeConflict { message: format!("type {} is documented differently", ty.name) });
+                }
+            }
+        }
+    }
+    Ok(types)
+}
+
+impl ScarType {
+    pub fn eq(&mut self, other: &Self) -> bool {
+        self.name == other.name && self.description == other.description && self.parent == other.parent
+        && self.fields == other.fields && self.builtin == other.builtin
+    }
+    pub fn merge_with(&mut self, other: &Self) {
+        if self.eq(other) {
+            return;
+        }
+        println!("Merging type {}", self.name);
+        self.description = match (self.description.clone(), other.description.clone()) {
+            (Some(_), Some(b)) => Some(b.clone()),
+            (None, Some(b)) => Some(b.clone()),
+            _ => self.description.clone()
+        };
+        self.parent = match (self.parent.clone(), other.parent.clone()) {
+            (Some(_), Some(b)) => Some(b.clone()),
+            (None, Some(b)) => Some(b.clone()),
+            _ => self.parent.clone()
+        };
+        self.fields = match (self.fields.len(), other.fields.len()) {
+            (_, i) if i > 0 => other.fields.clone(),
+            _ => self.fields.clone()
+        };
+        // A documented type replaces the built-in one
+        self.builtin = self.builtin && other.builtin;
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn can_merge_with_policy() {
+        use super::MergePolicy;
+        let main = crate::scardoc::generate_scardoc("scar").unwrap();
+        let mut second = main.clone();
+        second.categories[0].category_functions[0].description_short = Some(String::from("Changed"));
+        let name = second.categories[0].category_functions[0].name.clone();
+        let get_description = |doc: &crate::scardoc::ScarDoc| doc.categories.iter()
+            .flat_map(|x| x.category_functions.iter())
+            .find(|x| x.name == name)
+            .and_then(|x| x.description_short.clone());
+
+        let merged = super::merge_scardoc_with_policy(&main, &second, MergePolicy::Override).unwrap();
+        assert_eq!(get_description(&merged).as_deref(), Some("Changed"));
+        let merged = super::merge_scardoc_with_policy(&main, &second, MergePolicy::KeepFirst).unwrap();
+        assert_eq!(get_description(&merged), get_description(&main));
+        assert!(super::merge_scardoc_with_policy(&main, &main, MergePolicy::Strict).is_ok());
+        assert!(matches!(super::merge_scardoc_with_policy(&main, &second, MergePolicy::Strict),
+            Err(crate::scarerror::ScarDocError::MergeConflict { .. })));
+    }
+
+    #[test]
+    fn can_merge_with_categoriser() {
+        let rules: crate::scarcategory::CategoryRules = toml::from_str("\
+            [[rules]]\n\
+            prefix = \"Util_Spawn\"\n\
+            category = \"Spawning\"\n\
+            [descriptions]\n\
+            Spawning = \"Spawning units\"\n").unwrap();
+        let categoriser = rules.get_categoriser().unwrap();
+        let main = crate::scardoc::generate_scardoc("scar").unwrap();
+        let mut second = crate::scardoc::ScarDoc::default();
+        second.categories.push(crate::scardoc::ScarDocCategory {
+            category_name: String::from("Util"),
+            category_description: Some(String::from("Utility functions")),
+            category_functions: Vec::new()
+        });
+
+        // Categories and descriptions of the rules are kept, the others come from the scardocs
+        let merged = super::merge_scardocs_with(&[main, second], super::MergePolicy::Override, &categoriser).unwrap();
+        let get_category = |name: &str| merged.categories.iter().find(|x| x.category_name == name);
+        let spawning = get_category("Spawning").unwrap();
+        assert_eq!(spawning.category_description.as_deref(), Some("Spawning units"));
+        assert!(spawning.category_functions.iter().any(|x| x.name == "Util_SpawnGroup"));
+        assert_eq!(get_category("Util").unwrap().category_description.as_deref(), Some("Utility functions"));
+    }
+
+    #[test]
+    fn refuses_unmigrated_scardoc() {
+        let main = crate::scardoc::ScarDoc::default();
+        let second = crate::scardoc::ScarDoc { format_version: 0, ..Default::default() };
+        assert!(matches!(super::merge_scardoc(&main, &second),
+            Err(crate::scarerror::ScarDocError::FormatVersionMismatch { version: 0 })));
+    }
+
+    #[test]
+    fn can_merge_several_scardocs() {
+        let first = crate::scardoc::generate_scardoc("scar").unwrap();
+        let get_global = |name: &str, value: &str| crate::scardoc::ScarGlobal {
+            name: name.to_string(),
+            value: Some(value.to_string()),
+            description: None,
+            global_type: None,
+            deprecated: None,
+            since: None
+        };
+        let second = crate::scardoc::ScarDoc { globals: vec![get_global("MAX_SQUADS", "10"), get_global("SECOND_ONLY", "1")], ..Default::default() };
+        let third = crate::scardoc::ScarDoc { globals: vec![get_global("MAX_SQUADS", "20")], ..Default::default() };
+
+        // Every scardoc is merged on top of the ones before it
+        let merged = super::merge_scardocs(&[first.clone(), second, third]).unwrap();
+        let get_value = |name: &str| merged.globals.iter().find(|x| x.name == name).and_then(|x| x.value.clone());
+        assert_eq!(get_value("MAX_SQUADS").as_deref(), Some("20"));
+        assert_eq!(get_value("SECOND_ONLY").as_deref(), Some("1"));
+        assert_eq!(merged.categories.len(), first.categories.len());
     }
+
+    #[test]
+    fn can_merge_enum_values() {
+        use crate::scarenum::{ScarEnum, ScarEnumValue};
+        let get_value = |name: &str, value: Option<&str>, description: Option<&str>| ScarEnumValue {
+            name: name.to_string(),
+            value: value.map(String::from),
+            description: description.map(String::from),
+            deprecated: None,
+            source_file: None,
+            source_line: None
+        };
+        let mut generated = ScarEnum {
+            name: String::from("TEAM_STATE"),
+            values: vec![get_value("IDLE", Some("0"), None), get_value("ATTACKING", Some("1"), Some("Moving"))],
+            description: None,
+            deprecated: None,
+            since: None,
+            table: true
+        };
+        let mut manual_value = get_value("IDLE", None, Some("Not doing anything"));
+        manual_value.deprecated = Some(crate::scarfile::ScarDeprecation::default());
+        let manual = ScarEnum {
+            name: String::from("TEAM_STATE"),
+            values: vec![manual_value, get_value("RETREATING", Some("2"), None)],
+            description: None,
+            deprecated: None,
+            since: None,
+            table: false
+        };
+
+        // Documenting a value keeps the others, and what the manual scardoc leaves out
+        generated.merge_with(&manual);
+        let values: Vec<(&str, Option<&str>, Option<&str>, bool)> = generated.values.iter()
+            .map(|x| (x.name.as_str(), x.value.as_deref(), x.description.as_deref(), x.deprecated.is_some()))
+            .collect();
+        assert_eq!(values, vec![
+            ("IDLE", Some("0"), Some("Not doing anything"), true),
+            ("ATTACKING", Some("1"), Some("Moving"), false),
+            ("RETREATING", Some("2"), None, false)
+        ]);
+        assert!(generated.table);
+    }
+
 }
@@ -2,7 +2,7 @@ use std::{fs::File, io::{BufReader, BufRead}, collections::HashMap};
 
 use regex::Regex;
 
-use crate::{scardoc::{ScarDoc, categorise_functions, ScarGlobal}, scarfile::{ScarFunction, ScarSourceFile}, scarenum::{ScarEnum, ScarEnumValue}};
+use crate::{scarerror::ScarDocError, scardoc::{ScarDoc, SCARDOC_FORMAT_VERSION, categorise_functions, ScarGlobal}, scarfile::{ScarFunction, ScarSourceFile}, scarenum::{ScarEnum, ScarEnumValue}};
 
 const MODE_SCARDOC_UNDEFINED:i32 = 0;
 const MODE_SCARDOC_FUNCTIONS:i32 = 1;
@@ -14,26 +14,24 @@ struct ScardumpUnknown {
     pub vals: Vec<String>
 }
 
-pub fn read_scardump(dump_file: String) -> Result<ScarDoc, String> {
+/// Reads a scardoc dump, listing everything in the order it appears in the dump.
+pub fn read_scardump(dump_file: String) -> Result<ScarDoc, ScarDocError> {
 
-    let file = File::open(&dump_file);
-    if file.is_err() {
-        return Err(String::from("Failed opening file: ")+&dump_file)
-    }
+    let file = File::open(&dump_file).map_err(|e| ScarDocError::io(&dump_file, e))?;
 
     // Compile the regex
     let re = Regex::new(r"(\w+)(\[.*?\])?=(\w+)\((\d+)\)").unwrap();
 
-    let reader = BufReader::new(file.unwrap());
+    let reader = BufReader::new(file);
     let mut mode = MODE_SCARDOC_UNDEFINED;
 
     let mut funcs = Vec::new();
     let mut globals = Vec::new();
     let mut unknowns = Vec::new();
 
-    for line in reader.lines() {
+    for (line_number, line) in (1..).zip(reader.lines()) {
         match line  {
-            Err(_) => return Err(String::from("Failed reading line of dump file")),
+            Err(e) => return Err(ScarDocError::io(&dump_file, e)),
             Ok(ln) => {
                 if ln.eq_ignore_ascii_case("[ScarDoc:Functions]") {
                     mode = MODE_SCARDOC_FUNCTIONS
@@ -60,7 +58,11 @@ pub fn read_scardump(dump_file: String) -> Result<ScarDoc, String> {
                                 }
                             }   
                         }
-                        _ => return Err(String::from("unknown mode given"))
+                        _ => return Err(ScarDocError::DumpFormat {
+                            file: dump_file,
+                            line: line_number,
+                            message: String::from("expected a [ScarDoc:Functions], [ScarDoc:Globals] or [ScarDoc:Unknowns] section")
+                        })
                     }
                 }
             }
@@ -69,12 +71,16 @@ pub fn read_scardump(dump_file: String) -> Result<ScarDoc, String> {
 
     let categories = categorise_functions(vec![ScarSourceFile{
         source_name: String::from("temp"),
-        functions: funcs
+        functions: funcs,
+        types: Vec::new(),
+        globals: Vec::new(),
+        enums: Vec::new(),
+        warnings: Vec::new()
     }]);
 
     let enums = map_unknowns_to_enum(unknowns);
 
-    Ok(ScarDoc{categories, enums, globals})
+    Ok(ScarDoc{format_version: SCARDOC_FORMAT_VERSION, categories, enums, globals, types: Vec::new()})
 
 }
 
@@ -88,7 +94,11 @@ fn read_scarfunction(ln: String) -> ScarFunction {
         return_type: None, 
         parameters: Vec::new(), 
         source_file: None, 
-        groups: Vec::new() 
+        source_line: None, 
+        groups: Vec::new(),
+        deprecated: None,
+        since: None,
+        see: Vec::new()
     }
 }
 
@@ -102,7 +112,9 @@ fn read_global(ln: String) -> Option<ScarGlobal> {
                 name:k.to_string(),
                 value:Some(v.to_string()),
                 description: None,
-                global_type: None
+                global_type: None,
+                deprecated: None,
+                since: None
             })
         }
     }
@@ -120,24 +132,48 @@ fn read_unknowns(ln: String, re: &Regex) -> Option<ScardumpUnknown> {
 }
 
 fn map_unknowns_to_enum(u:Vec<ScardumpUnknown>) -> Vec<ScarEnum> {
-    let mut map: HashMap<String, Vec<(String,String)>> = HashMap::new();
+    // Enums are kept in the order they're first seen in the dump
+    let mut enums: Vec<ScarEnum> = Vec::new();
+    let mut map: HashMap<String, usize> = HashMap::new();
     for entry in u {
         if entry.is_enum {
-            let enum_value_name = entry.vals.get(0).unwrap();
+            let enum_value_name = entry.vals.first().unwrap();
             let enum_name = entry.vals.get(1).unwrap();
             let enum_value_number = entry.vals.get(2).unwrap();
-            map.entry(enum_name.clone())
-            .or_insert_with(|| vec![(enum_value_name.to_string(), enum_value_number.to_string())])
-            .push((enum_value_name.to_string(), enum_value_number.to_string()))
+            let idx = *map.entry(enum_name.clone())
+            .or_insert_with(|| {
+                enums.push(ScarEnum { name: enum_name.clone(), values: Vec::new(), description: None, deprecated: None, since: None, table: false });
+                enums.len() - 1
+            });
+            enums[idx].values.push(ScarEnumValue{ 
+                name: enum_value_name.to_string(), 
+                value: Some(enum_value_number.to_string()),
+                description: None,
+                deprecated: None,
+                source_file: None,
+                source_line: None
+            })
+        }
+    }
+    enums
+}
+
+mod tests {
+
+    #[test]
+    fn can_read_scardump_enums() {
+        let doc = super::read_scardump(String::from("scar/dump.txt")).unwrap();
+        let command_type = doc.enums.iter().find(|x| x.name == "EntityCommandType").unwrap();
+        assert!(command_type.values.iter().any(|x| x.name == "CMD_Casualty" && x.value.as_deref() == Some("27")));
+
+        // Every value is listed once, unlike the unversioned scardocs migrated by 'migrate_v0_to_v1'
+        for enum_def in doc.enums.iter() {
+            let mut names: Vec<&str> = enum_def.values.iter().map(|x| x.name.as_str()).collect();
+            let count = names.len();
+            names.sort();
+            names.dedup();
+            assert_eq!(names.len(), count, "enum {} lists a value more than once", enum_def.name);
         }
     }
-    map.into_iter().map(|x| {
-        let values = x.1.into_iter().map(|y| ScarEnumValue{ 
-            name: y.0, 
-            value: Some(y.1)
-        }).collect();
-        ScarEnum{
-            name: x.0,
-            values
-    }}).collect()
+
 }
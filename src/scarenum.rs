@@ -1,14 +1,34 @@
 use serde::{Serialize, Deserialize};
 
+use crate::scarfile::ScarDeprecation;
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ScarEnum {
     pub name: String,
-    pub values: Vec<ScarEnumValue>
+    pub values: Vec<ScarEnumValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<ScarDeprecation>,
+    /// The game version the enum was introduced in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+    /// Whether the values are fields of a table named after the enum, rather than globals.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub table: bool
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ScarEnumValue {
     pub name: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub value: Option<String>
+    pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<ScarDeprecation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_line: Option<usize>
 }
@@ -0,0 +1,84 @@
+use std::{fmt, io, error::Error};
+
+use crate::scardoc::SCARDOC_FORMAT_VERSION;
+
+/// Errors raised while generating, reading, merging or exporting scardocs.
+#[derive(Debug)]
+pub enum ScarDocError {
+    /// A file or directory could not be read or written.
+    Io { path: String, source: io::Error },
+    /// A scardoc file could not be (de)serialised.
+    Json { path: String, source: serde_json::Error },
+    /// A scardoc comment in a scar file could not be parsed.
+    Parse { file: String, line: usize, message: String },
+    /// A scardoc dump file is not in the expected format.
+    DumpFormat { file: String, line: usize, message: String },
+    /// A scardoc file was written by a newer version of the generator.
+    UnsupportedVersion { path: String, version: u64 },
+    /// Two scardocs could not be merged.
+    MergeConflict { message: String },
+    /// A scardoc of another format version was merged without being migrated first.
+    FormatVersionMismatch { version: u32 },
+    /// A scardoc file doesn't match the scardoc schema.
+    Invalid { path: String, errors: Vec<String> },
+    /// A project configuration file could not be parsed.
+    Config { path: String, source: toml::de::Error },
+    /// A category rule is invalid.
+    InvalidRule { category: String, message: String },
+    /// A glob pattern selecting scar files is invalid.
+    Pattern { pattern: String, source: globset::Error },
+    /// A pattern in a `.scardocignore` file uses syntax that isn't supported.
+    UnsupportedPattern { path: String, line: usize, pattern: String },
+    /// A directory could not be watched for changes.
+    Watch { path: String, source: notify::Error }
+}
+
+impl ScarDocError {
+    pub fn io<P: ToString>(path: P, source: io::Error) -> Self {
+        ScarDocError::Io { path: path.to_string(), source }
+    }
+    pub fn json<P: ToString>(path: P, source: serde_json::Error) -> Self {
+        ScarDocError::Json { path: path.to_string(), source }
+    }
+    pub fn parse<P: ToString, M: ToString>(file: P, line: usize, message: M) -> Self {
+        ScarDocError::Parse { file: file.to_string(), line, message: message.to_string() }
+    }
+}
+
+impl fmt::Display for ScarDocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScarDocError::Io { path, source } => write!(f, "failed accessing '{}': {}", path, source),
+            ScarDocError::Json { path, source } => write!(f, "invalid scardoc '{}': {}", path, source),
+            ScarDocError::Parse { file, line, message } => write!(f, "{}:{}: {}", file, line, message),
+            ScarDocError::DumpFormat { file, line, message } => write!(f, "{}:{}: invalid dump: {}", file, line, message),
+            ScarDocError::UnsupportedVersion { path, version } => write!(f,
+                "scardoc '{}' has format version {} but this generator only supports up to version {}, please update the generator",
+                path, version, SCARDOC_FORMAT_VERSION),
+            ScarDocError::MergeConflict { message } => write!(f, "merge conflict: {}", message),
+            ScarDocError::FormatVersionMismatch { version } => write!(f,
+                "cannot merge scardoc with format version {} into format version {}, it must be migrated by loading it first",
+                version, SCARDOC_FORMAT_VERSION),
+            ScarDocError::Invalid { path, errors } => write!(f, "scardoc '{}' is not valid: {}", path, errors.join(", ")),
+            ScarDocError::Config { path, source } => write!(f, "invalid configuration '{}': {}", path, source),
+            ScarDocError::InvalidRule { category, message } => write!(f, "invalid rule for category '{}': {}", category, message),
+            ScarDocError::Pattern { pattern, source } => write!(f, "invalid pattern '{}': {}", pattern, source),
+            ScarDocError::UnsupportedPattern { path, line, pattern } => write!(f,
+                "{}:{}: negated pattern '{}' is not supported", path, line, pattern),
+            ScarDocError::Watch { path, source } => write!(f, "failed watching '{}': {}", path, source)
+        }
+    }
+}
+
+impl Error for ScarDocError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ScarDocError::Io { source, .. } => Some(source),
+            ScarDocError::Json { source, .. } => Some(source),
+            ScarDocError::Config { source, .. } => Some(source),
+            ScarDocError::Pattern { source, .. } => Some(source),
+            ScarDocError::Watch { source, .. } => Some(source),
+            _ => None
+        }
+    }
+}
@@ -1,10 +1,24 @@
-use std::{path::Path, fs::File, io::{BufReader, BufRead}};
+use std::{fmt, fs::File, io::{BufReader, BufRead}};
 use serde::{Serialize, Deserialize};
 
+use crate::scardoc::ScarGlobal;
+use crate::scarenum::{ScarEnum, ScarEnumValue};
+use crate::scarerror::ScarDocError;
+use crate::scartype::{ScarType, ScarTypeField};
+
 #[derive(Serialize, Deserialize)]
 pub struct ScarSourceFile {
     pub source_name: String,
-    pub functions: Vec<ScarFunction>
+    pub functions: Vec<ScarFunction>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub types: Vec<ScarType>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub globals: Vec<ScarGlobal>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub enums: Vec<ScarEnum>,
+    /// Problems found in the file that didn't stop it from being read.
+    #[serde(skip)]
+    pub warnings: Vec<ScarDocError>
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -24,8 +38,41 @@ pub struct ScarFunction {
     pub parameters: Vec<ScarParameter>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub source_file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_line: Option<usize>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub groups: Vec<String>
+    pub groups: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<ScarDeprecation>,
+    /// The game version the function was introduced in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+    /// The names of related functions, enums, globals or types.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub see: Vec<String>
+}
+
+/// Marks a function, enum or global that shouldn't be used anymore.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct ScarDeprecation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// The name of what should be used instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replacement: Option<String>
+}
+
+impl fmt::Display for ScarDeprecation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "deprecated")?;
+        if let Some(reason) = &self.reason {
+            write!(f, ": {}", reason)?;
+        }
+        if let Some(replacement) = &self.replacement {
+            write!(f, ", use {} instead", replacement)?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -34,127 +81,749 @@ pub struct ScarParameter {
     pub arg_type: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub arg_description: Option<String>,
-    pub arg_required: bool
+    pub arg_required: bool,
+    /// The value an optional parameter has when it's not given, as written in the Lua code.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arg_default: Option<String>
 }
 
-pub fn get_scar_sourcefile(file_path: String) -> Result<ScarSourceFile, String> {
-
-    // Ensure path exists
-    if !Path::new(&file_path).exists() {
-        return Result::Err("file already exists".to_string());
-    }
+pub fn get_scar_sourcefile(file_path: String) -> Result<ScarSourceFile, ScarDocError> {
 
     // Open the file
-    let file = File::open(&file_path);
-    if file.is_err() {
-        return Result::Err("file failed to open for reading".to_string());
-    }
+    let file = File::open(&file_path).map_err(|e| ScarDocError::io(&file_path, e))?;
+    get_scar_sourcefile_from_reader(file_path, BufReader::new(file))
 
-    // Collect functions
-    match get_scar_functions(file.unwrap(), &file_path) {
+}
+
+/// Reads the scardoc comments of a scar file whose content is read from `reader`.
+pub fn get_scar_sourcefile_from_reader<R: BufRead>(file_path: String, reader: R) -> Result<ScarSourceFile, ScarDocError> {
+
+    // Collect functions, types, globals and enums
+    let mut warnings = Vec::new();
+    let mut types = Vec::new();
+    let mut globals = Vec::new();
+    let mut enums = Vec::new();
+    match get_scar_functions(reader, &file_path, &mut types, &mut globals, &mut enums, &mut warnings) {
         Err(e) => Err(e),
         Ok(funcs) => Ok(ScarSourceFile{
             source_name: file_path,
-            functions: funcs
+            functions: funcs,
+            types,
+            globals,
+            enums,
+            warnings
         })
     }
 
 }
 
-fn get_scar_functions(file: File, scar_source: &String) -> Result<Vec<ScarFunction>, String> {
+fn get_scar_functions<R: BufRead>(reader: R, scar_source: &str, types: &mut Vec<ScarType>, globals: &mut Vec<ScarGlobal>, enums: &mut Vec<ScarEnum>, warnings: &mut Vec<ScarDocError>) -> Result<Vec<ScarFunction>, ScarDocError> {
 
-    let reader = BufReader::new(file);
     let mut funcs: Vec<ScarFunction> = Vec::new();
 
     let mut doc_data: Vec<String> = Vec::new();
-    for line in reader.lines() {
+    let mut doc_line = 0;
+    let mut body_defaults: Option<ParameterDefaults> = None;
+    let mut enum_table: Option<EnumTable> = None;
+    let mut block_end: Option<String> = None;
+    for (line_number, line) in (1..).zip(reader.lines()) {
         match line {
-            Err(_) => return Err("failed to read line".to_string()),
+            Err(e) => return Err(ScarDocError::io(scar_source, e)),
             Ok(ln) => {
-                if ln.len() == 0 {
+                // The members of an enum may span several lines, up to the one closing its table
+                if let Some(table) = enum_table.as_mut() {
+                    if !table.read_line(&ln, line_number) {
+                        enums.extend(enum_table.take().map(|x| x.scar_enum));
+                    }
+                    continue;
+                }
+                // The start of the body of the last documented function may give its optional parameters a default value
+                if let Some(defaults) = body_defaults.as_mut() {
+                    if defaults.read_line(&ln) {
+                        continue;
+                    }
+                    defaults.apply(funcs.last_mut());
+                    body_defaults = None;
+                }
+                // Every line of a block comment up to its closing bracket is a scardoc line, without the `--? ` prefix
+                let block = match block_end.take() {
+                    Some(end) => Some((end, ln.as_str())),
+                    None => get_block_comment_start(&ln)
+                };
+                if let Some((end, content)) = block {
+                    let (content, is_closed) = match content.split_once(end.as_str()) {
+                        Some((content, _)) => (content.trim(), true),
+                        None => (content.trim(), false)
+                    };
+                    if !content.is_empty() {
+                        if is_type_definition(&doc_data) && is_type_definition(&[content.to_string()]) {
+                            add_scar_type(&doc_data, scar_source, doc_line, types, warnings);
+                            doc_data.clear();
+                        }
+                        if doc_data.is_empty() {
+                            doc_line = line_number;
+                        }
+                        doc_data.push(content.to_string());
+                    }
+                    if !is_closed {
+                        block_end = Some(end);
+                    }
+                    continue;
+                }
+                // Unlike function documentation, type definitions end at the first empty line or the next definition
+                let directive = match ln.strip_prefix("--? ") {
+                    Some(content) => Some(content.trim().to_string()),
+                    None => get_emmylua_content(&ln).and_then(|x| get_emmylua_directive(x, &doc_data))
+                };
+                if is_type_definition(&doc_data) && (ln.is_empty() || directive.as_ref().is_some_and(|x| is_type_definition(std::slice::from_ref(x)))) {
+                    add_scar_type(&doc_data, scar_source, doc_line, types, warnings);
+                    doc_data.clear();
+                }
+                if ln.is_empty() {
                     continue;
                 }
-                if ln.starts_with("--? ") {
-                    let content = (&ln[3..]).trim();
-                    doc_data.push(content.to_string());
-                } else if ln.trim().starts_with("function") {
-                    if doc_data.len() == 0 {
+                if doc_data.is_empty() {
+                    doc_line = line_number;
+                }
+                if ln.starts_with("--? ") || get_emmylua_content(&ln).is_some() {
+                    doc_data.extend(directive);
+                } else if is_type_definition(&doc_data) {
+                    // Type definitions document the types used by functions rather than the code following them
+                    add_scar_type(&doc_data, scar_source, doc_line, types, warnings);
+                    doc_data.clear();
+                } else if is_function_declaration(&ln) {
+                    if doc_data.is_empty() {
                         continue;
                     }
                     let scardoc_data = doc_data.clone();
-                    match get_scar_function(ln, scardoc_data) {
-                        Err(e) => if e.starts_with("fatal:") { return Err(e) } else { println!("{}", e) },
-                        Ok(mut f) => {
-                            f.source_file = Some(scar_source.clone());
-                            funcs.push(f)
+                    match get_scar_function(ln, scardoc_data, scar_source, line_number) {
+                        // A badly documented function shouldn't stop the rest of the file from being documented
+                        Err(e) => warnings.push(e),
+                        Ok(f) => {
+                            funcs.push(f);
+                            body_defaults = Some(ParameterDefaults::default());
                         }
                     }
                     doc_data.clear();
                 } else {
+                    // Documented assignments outside of functions declare globals, or enums if they're tables marked with @enum
+                    if doc_data.iter().any(|x| x == "@enum" || x.starts_with("@enum ")) {
+                        match EnumTable::new(&ln, line_number, scar_source, &doc_data) {
+                            None => warnings.push(ScarDocError::parse(scar_source, line_number, "expected a table after @enum")),
+                            Some(table) if table.is_closed() => enums.push(table.scar_enum),
+                            Some(table) => enum_table = Some(table)
+                        }
+                    } else if !doc_data.is_empty() && !ln.starts_with(char::is_whitespace) {
+                        globals.extend(get_scar_global(&ln, &doc_data));
+                    }
                     doc_data.clear()
                 }
             }
         }
     }
+    if is_type_definition(&doc_data) {
+        add_scar_type(&doc_data, scar_source, doc_line, types, warnings);
+    }
+    if let Some(defaults) = body_defaults {
+        defaults.apply(funcs.last_mut());
+    }
+    if block_end.is_some() {
+        warnings.push(ScarDocError::parse(scar_source, doc_line, "the block comment is never closed"));
+    }
+    if let Some(table) = enum_table {
+        warnings.push(ScarDocError::parse(scar_source, doc_line, format!("the table of enum {} is never closed", table.scar_enum.name)));
+    }
 
     Ok(funcs)
 
 }
 
+/// Gets the closing bracket and the rest of the line if the line opens a scardoc block comment,
+/// like `--[[?` closed by `]]` or `--[==[?` closed by `]==]`.
+pub(crate) fn get_block_comment_start(ln: &str) -> Option<(String, &str)> {
+    let rest = ln.strip_prefix("--[")?;
+    let level = rest.len() - rest.trim_start_matches('=').len();
+    let content = rest[level..].strip_prefix("[?")?;
+    Some((format!("]{}]", "=".repeat(level)), content))
+}
+
+fn get_emmylua_content(ln: &str) -> Option<&str> {
+    let content = ln.trim_start().strip_prefix("---")?;
+    // Lines of dashes are commonly used as separators rather than documentation
+    if content.starts_with('-') {
+        None
+    } else {
+        Some(content.trim())
+    }
+}
+
+/// Converts an EmmyLua/LuaLS annotation line into the equivalent scardoc directive line.
+///
+/// Description lines become `@shortdesc` (the first one) and `@extdesc` lines, while `@param`, `@return`, `@class`,
+/// `@deprecated`, `@since`, `@see` and `@enum` are kept as is and `@field name type` becomes `@field Type name`. Any other annotation is ignored.
+fn get_emmylua_directive(content: &str, doc_data: &[String]) -> Option<String> {
+    if content.is_empty() {
+        return None;
+    }
+    let is_kept = ["@param ", "@return ", "@class ", "@since ", "@see ", "@enum "].iter().any(|x| content.starts_with(x));
+    if is_kept || content == "@deprecated" || content.starts_with("@deprecated ") || content == "@enum" {
+        return Some(content.to_string());
+    }
+    if let Some(field) = content.strip_prefix("@field ") {
+        let (name, rest) = field.trim().split_once(char::is_whitespace)?;
+        let (lua_type, description) = split_type(rest);
+        let description = description.trim_start_matches('#').trim();
+        return Some(format!("@field {} {} {}", get_scar_type(lua_type.trim_end_matches('?')), name.trim_end_matches('?'), description).trim_end().to_string());
+    }
+    if content.starts_with('@') {
+        return None;
+    }
+    if doc_data.iter().any(|x| x.starts_with("@shortdesc")) {
+        Some(format!("@extdesc {}", content))
+    } else {
+        Some(format!("@shortdesc {}", content))
+    }
+}
+
+/// Maps the Lua type names used by EmmyLua/LuaLS to their scar equivalent.
+fn get_scar_type(lua_type: &str) -> String {
+    lua_type.split('|')
+        .filter(|x| *x != "nil")
+        .map(|x| match x {
+            "number" => "Real",
+            "integer" => "Integer",
+            "boolean" => "Boolean",
+            "string" => "String",
+            "table" => "LuaTable",
+            "function" => "LuaFunction",
+            "any" => "Any",
+            _ => x
+        })
+        .collect::<Vec<&str>>()
+        .join("|")
+}
+
+/// Splits a type from what follows it, keeping the spaces inside EmmyLua/LuaLS types
+/// like `table<string, number>` or `fun(a: number): boolean`.
+fn split_type(content: &str) -> (&str, &str) {
+    let content = content.trim();
+    let mut depth = 0;
+    let mut last = ' ';
+    for (i, c) in content.char_indices() {
+        match c {
+            '<' | '(' | '{' | '[' => depth += 1,
+            '>' | ')' | '}' | ']' => depth -= 1,
+            // A space after ':', ',' or '|' continues the type, like the return type of a function type
+            _ if c.is_whitespace() && depth <= 0 && !matches!(last, ':' | ',' | '|') => return (&content[..i], content[i..].trim()),
+            _ => {}
+        }
+        if !c.is_whitespace() {
+            last = c;
+        }
+    }
+    (content, "")
+}
+
+/// Parses an EmmyLua/LuaLS `@param name[?] Type [description]` annotation.
+fn get_emmylua_parameter(content: &str) -> Option<ScarParameter> {
+    let (name, rest) = content.trim().split_once(char::is_whitespace)?;
+    let (lua_type, description) = split_type(rest);
+    let description = Some(description.trim_start_matches('#').trim()).filter(|x| !x.is_empty());
+    let optional = name.ends_with('?') || lua_type.ends_with('?') || lua_type.split('|').any(|x| x == "nil");
+    let arg_type = get_scar_type(lua_type.trim_end_matches('?'));
+    Some(ScarParameter {
+        arg_name: name.trim_end_matches('?').to_string(),
+        arg_type: if arg_type.is_empty() { String::from("Any") } else { arg_type },
+        arg_description: description.map(|x| x.to_string()),
+        arg_required: !optional,
+        arg_default: None
+    })
+}
+
+fn add_scar_type(type_data: &[String], scar_source: &str, line: usize, types: &mut Vec<ScarType>, warnings: &mut Vec<ScarDocError>) {
+    match get_scar_type_definition(type_data, scar_source, line) {
+        Err(e) => warnings.push(e),
+        Ok(ty) => types.push(ty)
+    }
+}
+
+fn is_type_definition(doc_data: &[String]) -> bool {
+    doc_data.iter().any(|x| x.starts_with("@type ") || x.starts_with("@class "))
+}
+
+/// Parses a `@type Name [: Parent]` (or `@class`) block along with its description and `@field Type name [description]` lines.
+fn get_scar_type_definition(type_data: &[String], scar_source: &str, line: usize) -> Result<ScarType, ScarDocError> {
+    let mut name = String::new();
+    let mut parent = None;
+    let mut description: Vec<String> = Vec::new();
+    let mut fields = Vec::new();
+
+    let mut is_extended_desc = false;
+    for dataline in type_data {
+        if let Some(content) = dataline.strip_prefix("@type ").or_else(|| dataline.strip_prefix("@class ")) {
+            let (type_name, type_parent) = content.split_once(':').unwrap_or((content, ""));
+            name = type_name.trim().to_string();
+            parent = Some(type_parent.trim().to_string()).filter(|x| !x.is_empty());
+            is_extended_desc = false
+        } else if let Some(content) = dataline.strip_prefix("@shortdesc") {
+            description.insert(0, content.trim().to_string());
+            is_extended_desc = false
+        } else if let Some(content) = dataline.strip_prefix("@extdesc") {
+            if !content.trim().is_empty() {
+                description.push(content.trim().to_string());
+            }
+            is_extended_desc = true
+        } else if let Some(content) = dataline.strip_prefix("@field ") {
+            let (field_type, rest) = Some(split_type(content)).filter(|x| !x.1.is_empty())
+                .ok_or_else(|| ScarDocError::parse(scar_source, line, format!("failed to parse field directive '{}'", content.trim())))?;
+            let (field_name, field_description) = rest.trim().split_once(char::is_whitespace).unwrap_or((rest.trim(), ""));
+            fields.push(ScarTypeField {
+                name: field_name.to_string(),
+                field_type: field_type.to_string(),
+                description: Some(field_description.trim().to_string()).filter(|x| !x.is_empty())
+            });
+            is_extended_desc = false
+        } else if is_extended_desc {
+            description.push(dataline.clone())
+        }
+    }
+
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(ScarDocError::parse(scar_source, line, format!("expected a single type name but found '{}'", name)));
+    }
+    Ok(ScarType {
+        name,
+        description: Some(description.join("\n")).filter(|x| !x.is_empty()),
+        parent,
+        fields,
+        builtin: false
+    })
+}
+
+/// Parses a documented top-level `NAME = value` line into a global, inferring its type from the value.
+///
+/// String values are stored without their quotes, like in dumps. Values that aren't literals, like function calls or
+/// tables spanning several lines, aren't stored.
+fn get_scar_global(ln: &str, global_data: &[String]) -> Option<ScarGlobal> {
+    let code = split_comment(ln).0.trim().trim_end_matches(';').trim_end();
+    let (name, value) = get_assignment(code)?;
+    let global_type = get_value_type(value);
+    let value = match global_type {
+        Some("String") => Some(value[1..value.len()-1].to_string()),
+        Some("LuaTable") if value.matches('{').count() != value.matches('}').count() => None,
+        // Functions aren't literals, and their body usually continues on the next lines
+        Some("LuaFunction") => None,
+        Some(_) => Some(value.to_string()),
+        None => None
+    };
+    Some(ScarGlobal {
+        name: name.to_string(),
+        value,
+        description: get_description(global_data),
+        global_type: global_type.map(|x| x.to_string()),
+        deprecated: global_data.iter().find_map(|x| x.strip_prefix("@deprecated")).map(get_deprecation),
+        since: global_data.iter().find_map(|x| x.strip_prefix("@since")).map(|x| x.trim().to_string()).filter(|x| !x.is_empty())
+    })
+}
+
+/// Reads the members of a table documented with `@enum`, which may span several lines like
+/// ```lua
+/// TEAM_STATE = {
+///     --? Not doing anything
+///     IDLE = 0,
+///     ATTACKING = 1, -- Moving towards the enemy
+/// }
+/// ```
+/// A member is described by the scardoc comments before it, which may also mark it `@deprecated`, or a comment after it on the same line.
+struct EnumTable {
+    scar_enum: ScarEnum,
+    /// The scardoc comments describing the next member.
+    member_data: Vec<String>,
+    source_file: String,
+    line_number: usize,
+    depth: i32
+}
+
+impl EnumTable {
+
+    /// Starts reading the `NAME = {` line of a table, the enum being named after the table as that's how scar refers to its values.
+    fn new(ln: &str, line_number: usize, scar_source: &str, enum_data: &[String]) -> Option<EnumTable> {
+        let (code, comment) = split_comment(ln);
+        let (name, value) = get_assignment(code.trim())?;
+        let members = value.strip_prefix('{')?;
+        let mut table = EnumTable {
+            scar_enum: ScarEnum {
+                name: name.to_string(),
+                values: Vec::new(),
+                description: get_description(enum_data),
+                deprecated: enum_data.iter().find_map(|x| x.strip_prefix("@deprecated")).map(get_deprecation),
+                since: enum_data.iter().find_map(|x| x.strip_prefix("@since")).map(|x| x.trim().to_string()).filter(|x| !x.is_empty()),
+                table: true
+            },
+            member_data: Vec::new(),
+            source_file: scar_source.to_string(),
+            line_number,
+            depth: 1
+        };
+        table.read_members(members, comment);
+        Some(table)
+    }
+
+    fn is_closed(&self) -> bool {
+        self.depth <= 0
+    }
+
+    /// Reads the next line of the table, returning false once the table is closed.
+    fn read_line(&mut self, ln: &str, line_number: usize) -> bool {
+        self.line_number = line_number;
+        let trimmed = ln.trim();
+        if let Some(content) = trimmed.strip_prefix("--? ") {
+            self.member_data.push(content.trim().to_string());
+        } else if let Some(content) = get_emmylua_content(trimmed) {
+            self.member_data.push(content.to_string());
+        } else {
+            let (code, comment) = split_comment(trimmed);
+            self.read_members(code, comment);
+        }
+        !self.is_closed()
+    }
+
+    fn read_members(&mut self, code: &str, comment: Option<&str>) {
+        let mut last_member = None;
+        // Commas and braces in strings like `"a, b"` don't separate or close members
+        let mut start = 0;
+        let mut parts = Vec::new();
+        for idx in get_unquoted_positions(code, |c, _| c == ',') {
+            parts.push(&code[start..idx]);
+            start = idx + 1;
+        }
+        parts.push(&code[start..]);
+        for part in parts {
+            // Only members of the table itself are enum values, not those of nested tables
+            let is_member = self.depth == 1;
+            let opens = get_unquoted_positions(part, |c, _| c == '{');
+            let closes = get_unquoted_positions(part, |c, _| c == '}');
+            self.depth += opens.len() as i32 - closes.len() as i32;
+            let member = part[..closes.first().copied().unwrap_or(part.len())].trim();
+            let Some((name, value)) = get_assignment(member).filter(|_| is_member) else {
+                continue;
+            };
+            // Like for globals only literals are kept, values like `OTHER.VALUE` are Lua code
+            let value = match get_value_type(value) {
+                Some("String") => Some(value[1..value.len()-1].to_string()),
+                Some("Boolean" | "Integer" | "Real") => Some(value.to_string()),
+                _ => None
+            };
+            let member_data: Vec<String> = self.member_data.drain(..).collect();
+            let description = member_data.iter()
+                .filter(|x| !x.starts_with('@') || x.starts_with("@shortdesc") || x.starts_with("@extdesc"))
+                .map(|x| x.trim_start_matches("@shortdesc").trim_start_matches("@extdesc").trim())
+                .filter(|x| !x.is_empty())
+                .collect::<Vec<&str>>()
+                .join("\n");
+            self.scar_enum.values.push(ScarEnumValue {
+                name: name.to_string(),
+                value,
+                description: Some(description).filter(|x| !x.is_empty()),
+                deprecated: member_data.iter().find_map(|x| x.strip_prefix("@deprecated")).map(get_deprecation),
+                source_file: Some(self.source_file.clone()),
+                source_line: Some(self.line_number)
+            });
+            last_member = Some(self.scar_enum.values.len() - 1);
+        }
+        let comment = comment.map(|x| x.trim()).filter(|x| !x.is_empty());
+        if let (Some(idx), Some(comment)) = (last_member, comment) {
+            self.scar_enum.values[idx].description.get_or_insert_with(|| comment.to_string());
+        }
+    }
+
+}
+
+/// Checks whether a line starts with the `function` keyword, rather than a name like `functionCount`.
+fn is_function_declaration(ln: &str) -> bool {
+    ln.trim().strip_prefix("function").is_some_and(|x| x.starts_with(|c: char| c.is_whitespace() || c == '('))
+}
+
+/// Infers the scar type of a Lua value, like `Integer` for `5` or `LuaTable` for `{}`.
+fn get_value_type(value: &str) -> Option<&'static str> {
+    let is_quoted = |quote: char| value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote);
+    if value == "true" || value == "false" {
+        Some("Boolean")
+    } else if value.parse::<i64>().is_ok() {
+        Some("Integer")
+    } else if value.parse::<f64>().is_ok_and(|x| x.is_finite()) {
+        Some("Real")
+    } else if is_quoted('"') || is_quoted('\'') {
+        Some("String")
+    } else if value.starts_with('{') {
+        Some("LuaTable")
+    } else if is_function_declaration(value) {
+        Some("LuaFunction")
+    } else {
+        None
+    }
+}
+
+/// Joins the `@shortdesc` and `@extdesc` lines of a documentation block, with the short description first.
+fn get_description(doc_data: &[String]) -> Option<String> {
+    let mut description: Vec<&str> = Vec::new();
+    let mut is_extended_desc = false;
+    for dataline in doc_data {
+        if let Some(content) = dataline.strip_prefix("@shortdesc") {
+            description.insert(0, content.trim());
+            is_extended_desc = false
+        } else if let Some(content) = dataline.strip_prefix("@extdesc") {
+            if !content.trim().is_empty() {
+                description.push(content.trim());
+            }
+            is_extended_desc = true
+        } else if dataline.starts_with('@') {
+            is_extended_desc = false
+        } else if is_extended_desc {
+            description.push(dataline)
+        }
+    }
+    Some(description.join("\n")).filter(|x| !x.is_empty())
+}
+
 fn get_scar_function_name(ln: String) -> Option<String> {
     let start = ln.find(' ')?+1;
     let end = ln.find('(')?;
     if start >= end {
         return None;
     }
-    return Some((&ln[start..end]).trim().to_string());
+    Some(ln[start..end].trim().to_string())
+}
+
+/// Gets the parameter names of a `function Name(a, b, ...)` line, if the parameter list ends on the same line.
+pub(crate) fn get_lua_parameters(ln: &str) -> Option<Vec<String>> {
+    let start = ln.find('(')?+1;
+    let end = start + ln[start..].find(')')?;
+    Some(ln[start..end].split(',').map(|x| x.trim().to_string()).filter(|x| !x.is_empty()).collect())
+}
+
+/// Compares the documented parameters of a function with the parameters of its Lua signature, describing every difference.
+///
+/// Parameters documented by type only (named like `arg1`) are only counted, as their actual name is unknown.
+pub(crate) fn get_signature_mismatches(func: &ScarFunction, lua_parameters: &[String]) -> Vec<String> {
+    let mut mismatches = Vec::new();
+    let documented: Vec<&str> = func.parameters.iter().map(|x| x.arg_name.as_str()).filter(|x| *x != "...").collect();
+    let declared: Vec<&str> = lua_parameters.iter().map(|x| x.as_str()).filter(|x| *x != "...").collect();
+
+    let documents_varargs = func.parameters.iter().any(|x| x.arg_name == "...");
+    let takes_varargs = lua_parameters.iter().any(|x| x == "...");
+    if documents_varargs && !takes_varargs {
+        mismatches.push(format!("{} documents varargs but doesn't take them", func.name));
+    } else if takes_varargs && !documents_varargs {
+        mismatches.push(format!("{} takes varargs but doesn't document them", func.name));
+    }
+
+    if documented.len() != declared.len() {
+        let plural = if declared.len() == 1 { "" } else { "s" };
+        mismatches.push(format!("{} takes {} parameter{} but documents {}", func.name, declared.len(), plural, documented.len()));
+        return mismatches;
+    }
+    for (idx, (doc_name, lua_name)) in (1..).zip(documented.iter().zip(declared.iter())) {
+        if doc_name != lua_name && *doc_name != format!("arg{}", idx) {
+            mismatches.push(format!("parameter {} of {} is named '{}' but documented as '{}'", idx, func.name, lua_name, doc_name));
+        }
+    }
+    mismatches
 }
 
-fn get_parameters(args: String, mandatory: bool) -> Option<Vec<ScarParameter>> {
-    let by_comma = args.split(',');
+/// Parses a section of arguments, naming the ones documented by type only after their position in the signature,
+/// starting from `first_index`.
+fn get_parameters(args: String, mandatory: bool, first_index: usize) -> Option<Vec<ScarParameter>> {
     let mut parameters = Vec::new();
-    let mut arg_index = 1;
-    for p in by_comma {
-        let pp = p.trim();
+    for (arg_index, p) in (first_index..).zip(split_arguments(&args).into_iter().filter(|x| !x.trim().is_empty())) {
+        // Optional parameters may be given a default value like `Real ypos = 0`
+        let (pp, arg_default) = match p.split_once('=') {
+            None => (p.trim(), None),
+            Some((param, _)) if mandatory => (param.trim(), None),
+            Some((param, value)) => (param.trim(), Some(value.trim().to_string()).filter(|x| !x.is_empty()))
+        };
         match pp.find(' ') {
             None => {
                 parameters.push(ScarParameter { 
                     arg_name: if pp == "..." { String::from("...") } else { format!("arg{}", arg_index) }, 
                     arg_type: if pp == "..." { String::from("Any") } else { pp.to_string() },
                     arg_description: None, 
-                    arg_required: mandatory 
+                    arg_required: mandatory,
+                    arg_default
                 })
             },
             Some(idx) => {
                 let ty_name = &pp[..idx];
                 let pa_name = &pp[idx+1..];
                 parameters.push(ScarParameter { 
-                    arg_name: pa_name.to_string(), 
+                    arg_name: pa_name.trim().to_string(), 
                     arg_type: ty_name.to_string(), 
                     arg_description: None, 
-                    arg_required: mandatory
+                    arg_required: mandatory,
+                    arg_default
                 })
             }
         }
-        arg_index+=1;
     }
     Some(parameters)
 }
 
+/// Splits arguments by the commas that aren't part of a default value, like the ones in `Position pos = World_Pos(0, 0, 0)`
+/// or `String sep = ","`.
+fn split_arguments(args: &str) -> Vec<&str> {
+    let mut arguments = Vec::new();
+    let mut start = 0;
+    for i in get_unquoted_positions(args, |c, depth| c == ',' && depth == 0) {
+        arguments.push(&args[start..i]);
+        start = i + 1;
+    }
+    arguments.push(&args[start..]);
+    arguments
+}
+
+/// Splits a line of code from the comment at its end, ignoring `--` inside strings like `URL = "a--b"`.
+fn split_comment(ln: &str) -> (&str, Option<&str>) {
+    match get_unquoted_positions(ln, |c, _| c == '-').into_iter().find(|&i| ln[i..].starts_with("--")) {
+        Some(idx) => (&ln[..idx], Some(&ln[idx+2..])),
+        None => (ln, None)
+    }
+}
+
+/// Gets the positions of the characters outside of strings that match `matches`, given how deeply they're nested in
+/// parentheses or braces.
+fn get_unquoted_positions(args: &str, matches: impl Fn(char, i32) -> bool) -> Vec<usize> {
+    let mut positions = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in args.char_indices() {
+        if let Some(q) = quote {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                _ if c == q => quote = None,
+                _ => {}
+            }
+            continue;
+        }
+        if c == '"' || c == '\'' {
+            quote = Some(c);
+            continue;
+        }
+        if matches(c, depth) {
+            positions.push(i);
+        }
+        match c {
+            '(' | '{' => depth += 1,
+            ')' | '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    positions
+}
+
+/// Reads the default values given to parameters at the start of a function body, in either of the forms
+/// ```lua
+/// if ypos == nil then ypos = World_GetHeightAt(xpos, zpos) end
+/// count = count or 1
+/// ```
+/// where the `if` may also span several lines.
+#[derive(Default)]
+struct ParameterDefaults {
+    defaults: Vec<(String, String)>,
+    /// The parameter checked by an `if` spanning several lines, along with the value once it's assigned.
+    pending: Option<(String, Option<String>)>
+}
+
+impl ParameterDefaults {
+
+    /// Reads the next line of the function body, returning false once the line doesn't assign a default value.
+    fn read_line(&mut self, ln: &str) -> bool {
+        let ln = ln.trim().trim_end_matches(';').trim_end();
+        // Comments are skipped, but not the documentation of the next function
+        if ln.is_empty() || (ln.starts_with("--") && !ln.starts_with("--? ") && get_emmylua_content(ln).is_none() && get_block_comment_start(ln).is_none()) {
+            return true;
+        }
+        match self.pending.take() {
+            Some((name, None)) => match get_assignment(ln).filter(|x| x.0 == name) {
+                Some((_, value)) => self.pending = Some((name, Some(value.to_string()))),
+                None => return false
+            },
+            Some((name, Some(value))) if ln == "end" => self.defaults.push((name, value)),
+            Some(_) => return false,
+            None => {
+                if let Some((name, then)) = get_nil_check(ln) {
+                    match then.strip_suffix("end").and_then(|x| get_assignment(x.trim())).filter(|x| x.0 == name) {
+                        Some((_, value)) => self.defaults.push((name.to_string(), value.to_string())),
+                        None if then.is_empty() => self.pending = Some((name.to_string(), None)),
+                        None => return false
+                    }
+                } else if let Some((name, value)) = get_assignment(ln) {
+                    match value.strip_prefix(name).and_then(|x| x.trim_start().strip_prefix("or ")) {
+                        Some(default) => self.defaults.push((name.to_string(), default.trim().to_string())),
+                        None => return false
+                    }
+                } else {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Sets the default value of the optional parameters of `func` that don't document one already.
+    fn apply(&self, func: Option<&mut ScarFunction>) {
+        let Some(func) = func else {
+            return;
+        };
+        for (name, value) in &self.defaults {
+            let param = func.parameters.iter_mut().find(|x| &x.arg_name == name && !x.arg_required && x.arg_default.is_none());
+            if let Some(param) = param {
+                param.arg_default = Some(value.clone());
+            }
+        }
+    }
+
+}
+
+/// Splits an `if name == nil then ...` line into the name and whatever follows `then`.
+fn get_nil_check(ln: &str) -> Option<(&str, &str)> {
+    let (name, rest) = ln.strip_prefix("if ")?.split_once("==")?;
+    let then = rest.trim_start().strip_prefix("nil")?.trim_start().strip_prefix("then")?;
+    Some((name.trim(), then.trim())).filter(|x| is_identifier(x.0))
+}
+
+/// Splits a `name = value` line into the name and value.
+fn get_assignment(ln: &str) -> Option<(&str, &str)> {
+    let (name, value) = ln.split_once('=')?;
+    let value = value.trim();
+    Some((name.trim(), value)).filter(|x| is_identifier(x.0) && !x.1.is_empty() && !x.1.starts_with('='))
+}
+
+/// Checks if `name` is a valid Lua name, like a variable or parameter name.
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => chars.all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        _ => false
+    }
+}
+
 fn get_scar_function_args(ln: String) -> Option<Vec<ScarParameter>> {
-    match ln.find('[') {
-        None => get_parameters(ln, true),
+    // The optional section starts at the first bracket that isn't part of a default value
+    match get_unquoted_positions(&ln, |c, depth| c == '[' && depth == 0).first().copied() {
+        None => get_parameters(ln, true, 1),
         Some(idx) => {
-            let mut mandatory_section = (&ln[..idx]).trim_end();
+            let mut mandatory_section = ln[..idx].trim_end();
             if mandatory_section.ends_with(",") {
                 mandatory_section = &mandatory_section[..(mandatory_section.len()-1)]
             }
-            let mut mandatory = get_parameters(mandatory_section.to_string(), true)?;
-            let mut optional_section = (&ln[idx+1..]).trim_end_matches("]");
+            let mut mandatory = get_parameters(mandatory_section.to_string(), true, 1)?;
+            let mut optional_section = ln[idx+1..].trim_end_matches("]");
             if optional_section.starts_with(",") {
                 optional_section = &optional_section[1..];
             }
-            match get_parameters(optional_section.to_string(), false) {
+            match get_parameters(optional_section.to_string(), false, mandatory.len() + 1) {
                 None => None,
                 Some(optionals) => {
                     mandatory.extend(optionals);
@@ -165,38 +834,66 @@ fn get_scar_function_args(ln: String) -> Option<Vec<ScarParameter>> {
     }
 }
 
-fn get_scar_function(func_name: String, func_data: Vec<String>) -> Result<ScarFunction, String> {
+/// Parses the documentation of the function declared by `func_name`, on line `line` of `scar_source`.
+fn get_scar_function(func_name: String, func_data: Vec<String>, scar_source: &str, line: usize) -> Result<ScarFunction, ScarDocError> {
     
     // Get complete script name
-    let name = get_scar_function_name(func_name).ok_or("expected scar function name but found none")?;
+    let name = get_scar_function_name(func_name).ok_or_else(|| ScarDocError::parse(scar_source, line, "expected scar function name but found none"))?;
 
     // Define function setup
     let mut description_short: Option<String> = None;
     let mut description_extended: Vec<String> = Vec::new();
     let mut return_type: Option<String> = None;
+    let mut return_description: Option<String> = None;
     let mut parameters: Vec<ScarParameter> = Vec::new();
+    let mut deprecated: Option<ScarDeprecation> = None;
+    let mut since: Option<String> = None;
+    let mut see: Vec<String> = Vec::new();
 
     // Populate data
     let mut is_extended_desc = false;
     for dataline in func_data {
-        if dataline.starts_with("@shortdesc") {
-            description_short = Some((&dataline[10..]).trim().to_string());
+        if let Some(content) = dataline.strip_prefix("@shortdesc") {
+            description_short = Some(content.trim().to_string());
             is_extended_desc = false
-        } else if dataline.starts_with("@extdesc") {
-            let content = (&dataline[8..]).trim().to_string();
-            if content.len() > 0 {
+        } else if let Some(content) = dataline.strip_prefix("@extdesc") {
+            let content = content.trim().to_string();
+            if !content.is_empty() {
                 description_extended.push(content);
             }
             is_extended_desc = true
-        } else if dataline.starts_with("@result") {
-            return_type = Some((&dataline[7..]).trim().to_string());
+        } else if let Some(content) = dataline.strip_prefix("@result") {
+            return_type = Some(content.trim().to_string());
             is_extended_desc = false
-        } else if dataline.starts_with("@args") {
-            let content = (&dataline[5..]).trim().to_string();
+        } else if let Some(content) = dataline.strip_prefix("@args") {
+            let content = content.trim().to_string();
             let err_content = content.clone();
-            let args = get_scar_function_args(content).ok_or(format!("failed to parse arguments directive '{}'", err_content))?;
+            let args = get_scar_function_args(content)
+                .ok_or_else(|| ScarDocError::parse(scar_source, line, format!("failed to parse arguments directive '{}'", err_content)))?;
             parameters.extend(args);
             is_extended_desc = false
+        } else if let Some(content) = dataline.strip_prefix("@param ") {
+            let param = get_emmylua_parameter(content.trim())
+                .ok_or_else(|| ScarDocError::parse(scar_source, line, format!("failed to parse param annotation '{}'", content.trim())))?;
+            parameters.push(param);
+            is_extended_desc = false
+        } else if let Some(content) = dataline.strip_prefix("@return ") {
+            let (ty, desc) = split_type(content);
+            return_type = Some(get_scar_type(ty.trim_end_matches('?')));
+            let desc = desc.split_once('#').map_or(desc, |x| x.1).trim();
+            if !desc.is_empty() {
+                return_description = Some(desc.to_string());
+            }
+            is_extended_desc = false
+        } else if let Some(content) = dataline.strip_prefix("@deprecated") {
+            deprecated = Some(get_deprecation(content));
+            is_extended_desc = false
+        } else if let Some(content) = dataline.strip_prefix("@since") {
+            since = Some(content.trim().to_string()).filter(|x| !x.is_empty());
+            is_extended_desc = false
+        } else if let Some(content) = dataline.strip_prefix("@see") {
+            see.extend(content.split([',', ' ']).filter(|x| !x.is_empty()).map(|x| x.to_string()));
+            is_extended_desc = false
         } else if is_extended_desc {
             description_extended.push(dataline)
         }
@@ -204,18 +901,34 @@ fn get_scar_function(func_name: String, func_data: Vec<String>) -> Result<ScarFu
 
     // Return result
     Ok(ScarFunction { 
-        name: name, 
+        name, 
         description_short, 
         description_extended, 
         example: None, 
-        return_description: None, 
+        return_description, 
         return_type, 
         parameters,
-        source_file: None,
-        groups: Vec::new()
+        source_file: Some(scar_source.to_string()),
+        source_line: Some(line),
+        groups: Vec::new(),
+        deprecated,
+        since,
+        see
     })
 }
 
+/// Parses the `[reason] [replacement]` of a `@deprecated` directive.
+///
+/// The last word is the replacement if it names a function or table, like `Util_ScarPosition` or `Player:GetTeam`.
+pub(crate) fn get_deprecation(content: &str) -> ScarDeprecation {
+    let content = content.trim();
+    let (reason, last) = content.rsplit_once(char::is_whitespace).unwrap_or(("", content));
+    // Replacements are names like `Util_ScarPosition` or `Player:GetPosition`, unlike version numbers like `1.2.0`
+    let is_replacement = last.contains(['_', '.', ':']) && last.split(['.', ':']).all(is_identifier);
+    let (reason, replacement) = if is_replacement { (reason.trim(), Some(last.to_string())) } else { (content, None) };
+    ScarDeprecation { reason: Some(reason.to_string()).filter(|x| !x.is_empty()), replacement }
+}
+
 mod tests {
 
     #[test]
@@ -228,8 +941,8 @@ mod tests {
         ];
         for name in names {
             let s = name.1.to_string();
-            if name.0 == "" {
-                assert_eq!(super::get_scar_function_name(s).is_none(), true)
+            if name.0.is_empty() {
+                assert!(super::get_scar_function_name(s).is_none())
             } else {
                 assert_eq!(super::get_scar_function_name(s).unwrap(), name.0.to_string())
             }
@@ -244,10 +957,12 @@ mod tests {
             (vec![("LuaTable", "arg1", true)], "LuaTable"),
             (vec![("SyncWeaponID", "weapon", true), ("PlayerID", "player", false)], "SyncWeaponID weapon, [PlayerID player]"),
             (vec![("String", "race", true), ("String", "race2", false), ("Any", "...", false)], "String race[, String race2, ...]"),
+            (vec![("Real", "arg1", true), ("Real", "arg2", false)], "Real[, Real]"),
+            (vec![("Real", "arg1", false), ("Real", "arg2", false)], "[Real, Real]"),
         ];
         for arg in args {
             let result = super::get_scar_function_args(arg.1.to_string());
-            assert_eq!(result.is_some(), true);
+            assert!(result.is_some());
             let content = result.unwrap();
             assert_eq!(content.len(), arg.0.len());
             for i in 0..arg.0.len() {
@@ -260,39 +975,419 @@ mod tests {
         }
     }
 
+    #[test]
+    fn can_get_parameter_defaults() {
+        let content = "\
+            --? @args Real xpos, Real zpos[, Real ypos, Integer count, String name = \"squad\"]\n\
+            function Util_ScarPos(xpos, zpos, ypos, count, name)\n\
+            \tif ypos == nil then\n\
+            \t\typos = World_GetHeightAt(xpos, zpos)\n\
+            \tend\n\
+            \t-- Spawn a single squad by default\n\
+            \tcount = count or 1\n\
+            \tif name == nil then name = \"other\" end\n\
+            \txpos = xpos or 0\n\
+            end\n\
+            \n\
+            --? @args Position pos = World_Pos(0, 0, 0)[, Real radius, String sep = \",\", String quote = '\\'']\n\
+            function Util_Radius(pos, radius, sep, quote)\n\
+            \tlocal area = 10\n\
+            \tradius = radius or area\n\
+            end\n";
+        let scarfile = super::get_scar_sourcefile_from_reader(String::from("defaults.scar"), content.as_bytes()).unwrap();
+        assert!(scarfile.warnings.is_empty());
+
+        // Defaults are only read from the start of the body and only apply to optional parameters
+        let defaults: Vec<Option<&str>> = scarfile.functions[0].parameters.iter().map(|x| x.arg_default.as_deref()).collect();
+        assert_eq!(defaults, vec![None, None, Some("World_GetHeightAt(xpos, zpos)"), Some("1"), Some("\"squad\"")]);
+        let defaults: Vec<Option<&str>> = scarfile.functions[1].parameters.iter().map(|x| x.arg_default.as_deref()).collect();
+        assert_eq!(defaults, vec![None, None, Some("\",\""), Some("'\\''")]);
+        assert!(super::is_identifier("_count2") && !super::is_identifier("2nd") && !super::is_identifier("été"));
+    }
+
+    #[test]
+    fn can_get_quoted_defaults() {
+        // Brackets and commas in strings or calls don't start the optional section or split arguments
+        let args = "String open = \"[\", Real x[, String sep = \", \", String close = \"]\", Position pos = World_Pos(0, 0, 0)]";
+        let parameters = super::get_scar_function_args(args.to_string()).unwrap();
+        let names: Vec<(&str, bool, Option<&str>)> = parameters.iter().map(|x| (x.arg_name.as_str(), x.arg_required, x.arg_default.as_deref())).collect();
+        assert_eq!(names, vec![
+            ("open", true, None),
+            ("x", true, None),
+            ("sep", false, Some("\", \"")),
+            ("close", false, Some("\"]\"")),
+            ("pos", false, Some("World_Pos(0, 0, 0)"))
+        ]);
+    }
+
+    #[test]
+    fn can_get_deprecations() {
+        let deprecations = [
+            ("", None, None),
+            ("Util_ScarPosition", None, Some("Util_ScarPosition")),
+            ("Spawns at the wrong height Player:GetPosition", Some("Spawns at the wrong height"), Some("Player:GetPosition")),
+            ("No longer needed.", Some("No longer needed."), None),
+            ("Removed in 1.2.0", Some("Removed in 1.2.0"), None),
+            ("Removed in v1.2", Some("Removed in v1.2"), None)
+        ];
+        for (content, reason, replacement) in deprecations {
+            let deprecation = super::get_deprecation(content);
+            assert_eq!(deprecation.reason.as_deref(), reason);
+            assert_eq!(deprecation.replacement.as_deref(), replacement);
+        }
+
+        let content = "\
+            --? @shortdesc Converts a 2D position.\n\
+            --? @deprecated Util_ScarPosition\n\
+            --? @since 1.2.0\n\
+            function Util_ScarPos(xpos, zpos)\n\
+            \n\
+            ---Gets the team of a player.\n\
+            ---@deprecated\n\
+            function Player_GetTeam(player)\n";
+        let scarfile = super::get_scar_sourcefile_from_reader(String::from("deprecated.scar"), content.as_bytes()).unwrap();
+        assert_eq!(scarfile.functions[0].deprecated.as_ref().unwrap().to_string(), "deprecated, use Util_ScarPosition instead");
+        assert_eq!(scarfile.functions[0].since, Some(String::from("1.2.0")));
+        assert_eq!(scarfile.functions[1].deprecated.as_ref().unwrap().to_string(), "deprecated");
+    }
+
+    #[test]
+    fn can_get_deprecations_without_replacement() {
+        // Only identifiers are taken as replacements, not versions or the end of a sentence
+        let content = "\
+            --? @deprecated Removed in 1.2.0\n\
+            function Util_ScarPos(xpos, zpos)\n\
+            \n\
+            ---@deprecated Use the new api.\n\
+            function Player_GetTeam(player)\n\
+            \n\
+            --? @deprecated 2.0\n\
+            function Player_GetRace(player)\n";
+        let scarfile = super::get_scar_sourcefile_from_reader(String::from("deprecated.scar"), content.as_bytes()).unwrap();
+        let deprecations: Vec<String> = scarfile.functions.iter().map(|x| x.deprecated.as_ref().unwrap().to_string()).collect();
+        assert_eq!(deprecations, vec!["deprecated: Removed in 1.2.0", "deprecated: Use the new api.", "deprecated: 2.0"]);
+        assert!(scarfile.functions.iter().all(|x| x.deprecated.as_ref().unwrap().replacement.is_none()));
+    }
+
+    #[test]
+    fn can_get_references() {
+        let content = "\
+            --? @shortdesc Converts a 2D position.\n\
+            --? @see World_Pos, World_GetHeightAt\n\
+            --? @see Position\n\
+            function Util_ScarPos(xpos, zpos)\n\
+            \n\
+            ---Gets the team of a player.\n\
+            ---@see Player_GetRace\n\
+            function Player_GetTeam(player)\n";
+        let scarfile = super::get_scar_sourcefile_from_reader(String::from("see.scar"), content.as_bytes()).unwrap();
+        assert_eq!(scarfile.functions[0].see, vec!["World_Pos", "World_GetHeightAt", "Position"]);
+        assert_eq!(scarfile.functions[1].see, vec!["Player_GetRace"]);
+    }
+
+    #[test]
+    fn can_get_block_comments() {
+        let content = "\
+            --[[? @shortdesc Converts a 2D position.\n\
+            @extdesc\n\
+            The height is looked up at the position.\n\
+            \n\
+            Use World_Pos for a 3D position.\n\
+            @args Real xpos, Real zpos\n\
+            @result Position\n\
+            ]]\n\
+            function Util_ScarPos(xpos, zpos)\n\
+            \n\
+            --[==[?\n\
+            @shortdesc Gets the first squad of an sgroup, like sgroup[1]]\n\
+            ]==]\n\
+            function SGroup_GetFirst(sgroup)\n\
+            \n\
+            --[[ Not a scardoc block ]]\n\
+            function Undocumented()\n\
+            \n\
+            --[[? @shortdesc The state of the mission. ]]\n\
+            g_state = {}\n";
+        let scarfile = super::get_scar_sourcefile_from_reader(String::from("x.scar"), content.as_bytes()).unwrap();
+        assert!(scarfile.warnings.is_empty());
+        assert_eq!(scarfile.functions.iter().map(|x| x.name.as_str()).collect::<Vec<&str>>(), vec!["Util_ScarPos", "SGroup_GetFirst"]);
+
+        let scar_pos = scarfile.functions.first().unwrap();
+        assert_eq!(scar_pos.description_short.as_deref(), Some("Converts a 2D position."));
+        assert_eq!(scar_pos.description_extended, vec!["The height is looked up at the position.", "Use World_Pos for a 3D position."]);
+        assert_eq!(scar_pos.parameters.len(), 2);
+        assert_eq!(scar_pos.return_type.as_deref(), Some("Position"));
+        assert_eq!(scar_pos.source_line, Some(9));
+
+        // Only the matching long bracket closes the block
+        assert_eq!(scarfile.functions[1].description_short.as_deref(), Some("Gets the first squad of an sgroup, like sgroup[1]]"));
+        assert_eq!(scarfile.globals.first().unwrap().description.as_deref(), Some("The state of the mission."));
+
+        let content = "--[[? @shortdesc Never closed\nfunction Util_ScarPos(xpos, zpos)\n";
+        let scarfile = super::get_scar_sourcefile_from_reader(String::from("x.scar"), content.as_bytes()).unwrap();
+        assert_eq!(scarfile.warnings.iter().map(|x| x.to_string()).collect::<Vec<String>>(), vec!["x.scar:1: the block comment is never closed"]);
+    }
+
+    #[test]
+    fn can_get_scar_globals() {
+
+        const GLOBALS_SCAR: &str = "scar/globals.scar";
+
+        let scarfile = super::get_scar_sourcefile(GLOBALS_SCAR.to_string()).unwrap();
+        assert!(scarfile.warnings.is_empty());
+        let globals: Vec<(&str, Option<&str>, Option<&str>)> = scarfile.globals.iter()
+            .map(|x| (x.name.as_str(), x.value.as_deref(), x.global_type.as_deref()))
+            .collect();
+        assert_eq!(globals, vec![
+            ("MAX_SQUADS", Some("5"), Some("Integer")),
+            ("SPAWN_DELAY", Some("2.5"), Some("Real")),
+            ("DEFAULT_BLUEPRINT", Some("riflemen_us"), Some("String")),
+            ("g_state", Some("{}"), Some("LuaTable")),
+            ("g_waves", None, Some("LuaTable")),
+            ("g_marker", None, None),
+            ("OnStart", None, Some("LuaFunction")),
+            ("functionCount", Some("0"), Some("Integer")),
+            ("DOCS_URL", Some("https://example.com/a--b"), Some("String"))
+        ]);
+
+        // Assert descriptions
+        let max_squads = scarfile.globals.first().unwrap();
+        assert_eq!(max_squads.description, Some("The most squads spawned at once.\nCounts every player.".to_string()));
+        assert_eq!(max_squads.since, Some("1.1".to_string()));
+        assert_eq!(scarfile.globals.get(2).unwrap().description, Some("The squad spawned if none is given.".to_string()));
+        assert!(scarfile.globals.get(5).unwrap().deprecated.is_some());
+
+    }
+
+    #[test]
+    fn can_get_scar_enums() {
+
+        const ENUMS_SCAR: &str = "scar/enums.scar";
+
+        let scarfile = super::get_scar_sourcefile(ENUMS_SCAR.to_string()).unwrap();
+        assert_eq!(scarfile.warnings.iter().map(|x| x.to_string()).collect::<Vec<String>>(), vec![
+            "scar/enums.scar:18: expected a table after @enum"
+        ]);
+        assert!(scarfile.globals.is_empty());
+        assert_eq!(scarfile.enums.iter().map(|x| x.name.as_str()).collect::<Vec<&str>>(), vec!["TEAM_STATE", "DIFFICULTIES"]);
+        let values: Vec<(&str, Option<&str>, Option<&str>)> = scarfile.enums.iter()
+            .flat_map(|x| x.values.iter())
+            .map(|x| (x.name.as_str(), x.value.as_deref(), x.description.as_deref()))
+            .collect();
+        assert_eq!(values, vec![
+            ("IDLE", Some("0"), Some("Not doing anything")),
+            ("ATTACKING", Some("1"), Some("Moving towards the enemy")),
+            ("RETREATING", Some("2"), None),
+            ("DEFENDING", Some("3"), None),
+            ("EASY", Some("easy"), None),
+            ("HARD", Some("hard"), None),
+            ("HARDEST", None, None)
+        ]);
+
+        // Assert enum documentation
+        let team_state = scarfile.enums.first().unwrap();
+        assert_eq!(team_state.description, Some("The states a team can be in.".to_string()));
+        assert!(team_state.table);
+        assert_eq!(team_state.values.iter().map(|x| x.source_line.unwrap()).collect::<Vec<usize>>(), vec![5, 6, 7, 9]);
+        assert!(team_state.values.iter().all(|x| x.source_file.as_deref() == Some(ENUMS_SCAR)));
+        assert_eq!(team_state.values.get(3).unwrap().deprecated.as_ref().map(|x| x.to_string()), Some(String::from("deprecated: Teams no longer defend")));
+        let difficulty = scarfile.enums.get(1).unwrap();
+        assert!(difficulty.table);
+        assert_eq!(difficulty.description, Some("The difficulties of the mission.".to_string()));
+        assert_eq!(difficulty.since, Some("1.2".to_string()));
+
+        // The table may be closed on the line declaring it
+        let content = "\
+--? @enum
+STATE = { ON = 1, OFF = 0 }
+";
+        let scarfile = super::get_scar_sourcefile_from_reader(String::from("x.scar"), content.as_bytes()).unwrap();
+        assert_eq!(scarfile.enums.first().unwrap().values.len(), 2);
+
+        // Commas, comments and braces in strings are part of the value
+        let content = "\
+--? @enum
+SEPARATORS = { COMMA = \"a, b\", -- Joins with a comma
+\tDASHES = \"x--y\",
+\tBRACE = \"}\", OTHER = 1
+}
+";
+        let scarfile = super::get_scar_sourcefile_from_reader(String::from("x.scar"), content.as_bytes()).unwrap();
+        assert!(scarfile.warnings.is_empty());
+        let values: Vec<(&str, Option<&str>, Option<&str>)> = scarfile.enums.first().unwrap().values.iter()
+            .map(|x| (x.name.as_str(), x.value.as_deref(), x.description.as_deref()))
+            .collect();
+        assert_eq!(values, vec![
+            ("COMMA", Some("a, b"), Some("Joins with a comma")),
+            ("DASHES", Some("x--y"), None),
+            ("BRACE", Some("}"), None),
+            ("OTHER", Some("1"), None)
+        ]);
+
+    }
+
     #[test]
     fn can_get_scar_sourcefile() {
 
         const SIMPLE_SCAR: &str = "scar/simple.scar";
 
         let result = super::get_scar_sourcefile(SIMPLE_SCAR.to_string());
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
 
         let scarfile = result.unwrap();
         assert_eq!(scarfile.source_name, SIMPLE_SCAR);
         assert_eq!(scarfile.functions.len(), 1);
 
         // Assert found function
-        let scarfn = scarfile.functions.get(0).unwrap();
+        let scarfn = scarfile.functions.first().unwrap();
         assert_eq!(scarfn.name, "Util_ScarPos");
         assert_eq!(scarfn.description_short, Some("Converts a 2D top down position to a 3D ScarPosition. returns Position, if y-height is nil, y-height = ground height, terrain ground or walkable".to_string()));
         assert_eq!(scarfn.description_extended.len(), 2); // We simply assert length and assume it worked
 
         // Assert arguments
         assert_eq!(scarfn.parameters.len(), 3);
-        assert_eq!(scarfn.parameters.get(0).unwrap().arg_name, "xpos");
-        assert_eq!(scarfn.parameters.get(0).unwrap().arg_type, "Real");
-        assert_eq!(scarfn.parameters.get(0).unwrap().arg_required, true);
+        assert_eq!(scarfn.parameters.first().unwrap().arg_name, "xpos");
+        assert_eq!(scarfn.parameters.first().unwrap().arg_type, "Real");
+        assert!(scarfn.parameters.first().unwrap().arg_required);
         assert_eq!(scarfn.parameters.get(1).unwrap().arg_name, "zpos");
         assert_eq!(scarfn.parameters.get(1).unwrap().arg_type, "Real");
-        assert_eq!(scarfn.parameters.get(1).unwrap().arg_required, true);
+        assert!(scarfn.parameters.get(1).unwrap().arg_required);
         assert_eq!(scarfn.parameters.get(2).unwrap().arg_name, "ypos");
         assert_eq!(scarfn.parameters.get(2).unwrap().arg_type, "Real");
-        assert_eq!(scarfn.parameters.get(2).unwrap().arg_required, true);
+        assert!(scarfn.parameters.get(2).unwrap().arg_required);
 
         // Assert return types
         assert_eq!(scarfn.return_type, Some("Position".to_string()));
 
     }
 
+    #[test]
+    fn can_get_emmylua_sourcefile() {
+
+        const EMMYLUA_SCAR: &str = "scar/emmylua.scar";
+
+        let result = super::get_scar_sourcefile(EMMYLUA_SCAR.to_string());
+        assert!(result.is_ok());
+
+        let scarfile = result.unwrap();
+        assert_eq!(scarfile.functions.len(), 1);
+
+        // Assert descriptions
+        let scarfn = scarfile.functions.first().unwrap();
+        assert_eq!(scarfn.name, "Util_SpawnGroup");
+        assert_eq!(scarfn.description_short, Some("Creates a squad group and spawns it at a position.".to_string()));
+        assert_eq!(scarfn.description_extended, vec!["The group is created empty if no blueprint is given.".to_string()]);
+
+        // Assert arguments
+        assert_eq!(scarfn.parameters.len(), 3);
+        assert_eq!(scarfn.parameters.first().unwrap().arg_name, "name");
+        assert_eq!(scarfn.parameters.first().unwrap().arg_type, "String");
+        assert_eq!(scarfn.parameters.first().unwrap().arg_description, Some("The name of the sgroup".to_string()));
+        assert_eq!(scarfn.parameters.get(1).unwrap().arg_type, "Position");
+        assert!(scarfn.parameters.get(1).unwrap().arg_description.is_none());
+        assert_eq!(scarfn.parameters.get(2).unwrap().arg_name, "count");
+        assert_eq!(scarfn.parameters.get(2).unwrap().arg_type, "Integer");
+        assert!(!scarfn.parameters.get(2).unwrap().arg_required);
+
+        // Assert return types
+        assert_eq!(scarfn.return_type, Some("SGroupID".to_string()));
+        assert_eq!(scarfn.return_description, Some("The created sgroup".to_string()));
+
+    }
+
+    #[test]
+    fn can_get_emmylua_compound_types() {
+        let content = "\
+            ---@class Wave\n\
+            ---@field spawns table<string, integer> Squads spawned per blueprint\n\
+            \n\
+            ---Runs a callback for every squad.\n\
+            ---@param squads table<string, SquadID> The squads by name\n\
+            ---@param callback fun(squad: SquadID, index: integer): boolean # Stops when returning false\n\
+            ---@return table<string, boolean> # Whether each squad was handled\n\
+            function Squads_ForEach(squads, callback)\n";
+        let scarfile = super::get_scar_sourcefile_from_reader(String::from("x.scar"), content.as_bytes()).unwrap();
+        assert!(scarfile.warnings.is_empty());
+
+        let field = scarfile.types.first().unwrap().fields.first().unwrap();
+        assert_eq!((field.name.as_str(), field.field_type.as_str()), ("spawns", "table<string, integer>"));
+        assert_eq!(field.description.as_deref(), Some("Squads spawned per blueprint"));
+
+        let scarfn = scarfile.functions.first().unwrap();
+        let parameters: Vec<(&str, &str, Option<&str>)> = scarfn.parameters.iter()
+            .map(|x| (x.arg_name.as_str(), x.arg_type.as_str(), x.arg_description.as_deref()))
+            .collect();
+        assert_eq!(parameters, vec![
+            ("squads", "table<string, SquadID>", Some("The squads by name")),
+            ("callback", "fun(squad: SquadID, index: integer): boolean", Some("Stops when returning false"))
+        ]);
+        assert_eq!(scarfn.return_type.as_deref(), Some("table<string, boolean>"));
+        assert_eq!(scarfn.return_description.as_deref(), Some("Whether each squad was handled"));
+    }
+
+    #[test]
+    fn can_report_parse_errors() {
+        let content = "\
+            --? @type Two Names\n\
+            \n\
+            --? @shortdesc Converts a 2D position.\n\
+            --? @param xpos\n\
+            function Util_ScarPos(xpos)\n";
+        let scarfile = super::get_scar_sourcefile_from_reader(String::from("x.scar"), content.as_bytes()).unwrap();
+        let warnings: Vec<(usize, String)> = scarfile.warnings.iter()
+            .map(|x| match x {
+                crate::scarerror::ScarDocError::Parse { line, message, .. } => (*line, message.clone()),
+                _ => panic!("expected a parse error")
+            })
+            .collect();
+        assert_eq!(warnings, vec![
+            (1, String::from("expected a single type name but found 'Two Names'")),
+            (5, String::from("failed to parse param annotation 'xpos'"))
+        ]);
+    }
+
+    #[test]
+    fn can_report_missing_sourcefile() {
+        let result = super::get_scar_sourcefile("scar/missing.scar".to_string());
+        match result {
+            Err(crate::scarerror::ScarDocError::Io { path, source }) => {
+                assert_eq!(path, "scar/missing.scar");
+                assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+            }
+            _ => panic!("expected an io error")
+        }
+    }
+
+    #[test]
+    fn can_get_scar_types() {
+
+        const TYPES_SCAR: &str = "scar/types.scar";
+
+        let scarfile = super::get_scar_sourcefile(TYPES_SCAR.to_string()).unwrap();
+        assert!(scarfile.functions.is_empty());
+        assert!(scarfile.warnings.is_empty());
+        assert_eq!(scarfile.types.len(), 2);
+
+        // Assert scardoc type
+        let position = scarfile.types.first().unwrap();
+        assert_eq!(position.name, "Position");
+        assert_eq!(position.description, Some("A 3D position in the world.".to_string()));
+        assert!(position.parent.is_none());
+        assert_eq!(position.fields.len(), 3);
+        assert_eq!(position.fields.get(1).unwrap().name, "y");
+        assert_eq!(position.fields.get(1).unwrap().field_type, "Real");
+        assert_eq!(position.fields.get(1).unwrap().description, Some("The height of the terrain".to_string()));
+
+        // Assert EmmyLua class
+        let group = scarfile.types.get(1).unwrap();
+        assert_eq!(group.name, "SpawnedGroup");
+        assert_eq!(group.description, Some("A squad group spawned at a position.".to_string()));
+        assert_eq!(group.parent, Some("SGroupID".to_string()));
+        assert_eq!(group.fields.first().unwrap().field_type, "Integer");
+        assert_eq!(group.fields.first().unwrap().description, Some("How many squads were spawned".to_string()));
+        assert_eq!(group.fields.get(1).unwrap().field_type, "Position");
+        assert!(group.fields.get(1).unwrap().description.is_none());
+
+    }
+
 }
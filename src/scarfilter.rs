@@ -0,0 +1,169 @@
+use std::{fs, io, path::Path};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+use crate::scarerror::ScarDocError;
+
+/// File in the root of a scar directory listing patterns of files and directories to skip, one per line.
+pub const SCARDOC_IGNORE_FILE: &str = ".scardocignore";
+
+/// Which files in a scar directory are read.
+///
+/// Patterns are globs matched against paths relative to the scar directory. Like in `.gitignore` files, a pattern
+/// without a `/` matches at any depth, a leading `/` anchors it to the scar directory and a trailing `/` only
+/// matches directories.
+#[derive(Clone, Default)]
+pub struct ScarFileFilter {
+    /// Patterns of the files to read, every file is read if empty.
+    pub include: Vec<String>,
+    /// Patterns of the files and directories to skip, on top of those in `.scardocignore`.
+    pub exclude: Vec<String>,
+    /// Extensions read on top of `scar`, like `lua`.
+    pub extensions: Vec<String>
+}
+
+/// A [`ScarFileFilter`] compiled for a scar directory.
+pub struct ScarFileMatcher {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    exclude_dirs: GlobSet,
+    extensions: Vec<String>
+}
+
+impl ScarFileFilter {
+
+    /// Compiles the filter for `dir_path`, adding the patterns in its `.scardocignore` file.
+    pub fn get_matcher<P: AsRef<Path>>(&self, dir_path: P) -> Result<ScarFileMatcher, ScarDocError> {
+        let mut exclude = GlobSetBuilder::new();
+        let mut exclude_dirs = GlobSetBuilder::new();
+        for pattern in &self.exclude {
+            add_pattern(pattern, &mut exclude, &mut exclude_dirs)
+                .map_err(|e| ScarDocError::Pattern { pattern: pattern.clone(), source: e })?;
+        }
+
+        let ignore_file = dir_path.as_ref().join(SCARDOC_IGNORE_FILE);
+        let ignore_path = ignore_file.display().to_string();
+        match fs::read_to_string(&ignore_file) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {},
+            Err(e) => return Err(ScarDocError::io(ignore_path, e)),
+            Ok(content) => for (line, pattern) in (1..).zip(content.lines().map(|x| x.trim())) {
+                if pattern.is_empty() || pattern.starts_with('#') {
+                    continue;
+                }
+                if pattern.starts_with('!') {
+                    return Err(ScarDocError::UnsupportedPattern { path: ignore_path, line, pattern: pattern.to_string() });
+                }
+                add_pattern(pattern, &mut exclude, &mut exclude_dirs)
+                    .map_err(|e| ScarDocError::Pattern { pattern: pattern.to_string(), source: e })?;
+            }
+        }
+
+        let include = match self.include.is_empty() {
+            true => None,
+            false => {
+                let mut include = GlobSetBuilder::new();
+                for pattern in &self.include {
+                    add_pattern(pattern, &mut include, &mut GlobSetBuilder::new())
+                        .map_err(|e| ScarDocError::Pattern { pattern: pattern.clone(), source: e })?;
+                }
+                Some(build_globset(include)?)
+            }
+        };
+
+        let mut extensions = vec![String::from("scar")];
+        extensions.extend(self.extensions.iter().map(|x| x.trim_start_matches('.').to_string()));
+
+        Ok(ScarFileMatcher { include, exclude: build_globset(exclude)?, exclude_dirs: build_globset(exclude_dirs)?, extensions })
+    }
+
+}
+
+impl ScarFileMatcher {
+
+    /// Checks if a directory, relative to the scar directory, is skipped along with everything in it.
+    pub fn is_excluded_dir(&self, dir_path: &Path) -> bool {
+        self.exclude.is_match(dir_path) || self.exclude_dirs.is_match(dir_path)
+    }
+
+    /// Checks if a file, relative to the scar directory, is read.
+    pub fn is_match(&self, file_path: &Path) -> bool {
+        let has_extension = file_path.extension()
+            .and_then(|x| x.to_str())
+            .is_some_and(|x| self.extensions.iter().any(|y| y.eq_ignore_ascii_case(x)));
+        let in_excluded_dir = file_path.ancestors()
+            .skip(1)
+            .any(|x| !x.as_os_str().is_empty() && self.is_excluded_dir(x));
+        has_extension && !in_excluded_dir
+            && !self.exclude.is_match(file_path)
+            && self.include.as_ref().is_none_or(|x| x.is_match(file_path))
+    }
+
+}
+
+/// Adds a pattern to `globs`, or to `dir_globs` if it only matches directories.
+fn add_pattern(pattern: &str, globs: &mut GlobSetBuilder, dir_globs: &mut GlobSetBuilder) -> Result<(), globset::Error> {
+    let trimmed = pattern.trim_end_matches('/');
+    let glob = match trimmed.strip_prefix('/') {
+        Some(anchored) => anchored.to_string(),
+        None if trimmed.contains('/') => trimmed.to_string(),
+        None => format!("**/{}", trimmed)
+    };
+    let glob = GlobBuilder::new(&glob).literal_separator(true).build()?;
+    if pattern.ends_with('/') {
+        dir_globs.add(glob);
+    } else {
+        globs.add(glob);
+    }
+    Ok(())
+}
+
+fn build_globset(builder: GlobSetBuilder) -> Result<GlobSet, ScarDocError> {
+    builder.build().map_err(|e| ScarDocError::Pattern { pattern: e.glob().unwrap_or_default().to_string(), source: e })
+}
+
+mod tests {
+
+    #[test]
+    fn can_filter_scar_files() {
+        use std::path::Path;
+        let filter = super::ScarFileFilter {
+            include: Vec::new(),
+            exclude: vec![String::from("tests/"), String::from("/_old"), String::from("*.gen.scar")],
+            extensions: vec![String::from(".lua")]
+        };
+        let matcher = filter.get_matcher("scar").unwrap();
+        assert!(matcher.is_match(Path::new("simple.scar")));
+        assert!(matcher.is_match(Path::new("ui/menu.lua")));
+        assert!(!matcher.is_match(Path::new("readme.txt")));
+        assert!(!matcher.is_match(Path::new("tests/simple.scar")));
+        assert!(!matcher.is_match(Path::new("ai/tests/simple.scar")));
+        assert!(!matcher.is_match(Path::new("_old/simple.scar")));
+        assert!(matcher.is_match(Path::new("ai/_old/simple.scar")));
+        assert!(!matcher.is_match(Path::new("ai/units.gen.scar")));
+
+        let filter = super::ScarFileFilter { include: vec![String::from("/ai/**")], ..Default::default() };
+        let matcher = filter.get_matcher("scar").unwrap();
+        assert!(matcher.is_match(Path::new("ai/squads/attack.scar")));
+        assert!(!matcher.is_match(Path::new("simple.scar")));
+    }
+
+    #[test]
+    fn can_read_ignore_file() {
+        let dir = std::env::temp_dir().join(format!("scardoc-filter-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("_old")).unwrap();
+        std::fs::copy("scar/simple.scar", dir.join("simple.scar")).unwrap();
+        std::fs::copy("scar/simple.scar", dir.join("_old").join("simple.scar")).unwrap();
+        std::fs::write(dir.join(super::SCARDOC_IGNORE_FILE), "# Outdated scripts\n_old/\n").unwrap();
+
+        let files = crate::scardoc::get_scar_files(&dir).unwrap();
+        assert_eq!(files, vec![dir.join("simple.scar").to_str().unwrap().to_string()]);
+
+        std::fs::write(dir.join(super::SCARDOC_IGNORE_FILE), "!_old/\n").unwrap();
+        assert!(matches!(crate::scardoc::get_scar_files(&dir), Err(crate::scarerror::ScarDocError::UnsupportedPattern { line: 1, .. })));
+
+        std::fs::write(dir.join(super::SCARDOC_IGNORE_FILE), "# Outdated scripts\n_old/[\n").unwrap();
+        assert!(matches!(crate::scardoc::get_scar_files(&dir), Err(crate::scarerror::ScarDocError::Pattern { .. })));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+}
@@ -0,0 +1,482 @@
+use std::{collections::{BTreeSet, HashMap}, fmt, fs};
+
+use crate::scardoc::ScarDoc;
+use crate::scarerror::ScarDocError;
+use crate::scarfile::{ScarDeprecation, ScarFunction, get_lua_parameters, get_signature_mismatches};
+use crate::scartype::get_builtin_types;
+
+/// A problem found in a scardoc that doesn't stop it from being generated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScarDiagnostic {
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub message: String
+}
+
+impl fmt::Display for ScarDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "{}:{}: {}", file, line, self.message),
+            (Some(file), None) => write!(f, "{}: {}", file, self.message),
+            _ => write!(f, "{}", self.message)
+        }
+    }
+}
+
+/// Runs every lint on a scardoc, returning the diagnostics in the order of the scardoc.
+pub fn lint_scardoc(doc: &ScarDoc) -> Vec<ScarDiagnostic> {
+    let mut diagnostics = lint_types(doc);
+    diagnostics.extend(lint_references(doc));
+    diagnostics
+}
+
+/// Checks that every parameter and return type is a known type, enum or class.
+///
+/// Unknown types are reported along with the closest known type, if any is close enough to be a typo.
+pub fn lint_types(doc: &ScarDoc) -> Vec<ScarDiagnostic> {
+    let known = get_known_types(doc);
+    let mut diagnostics = Vec::new();
+    for func in doc.categories.iter().flat_map(|x| x.category_functions.iter()) {
+        let types = func.parameters.iter()
+            .map(|x| (format!("parameter '{}'", x.arg_name), &x.arg_type))
+            .chain(func.return_type.iter().map(|x| (String::from("return type"), x)));
+        for (usage, scar_type) in types {
+            for name in get_type_names(scar_type).into_iter().filter(|x| !known.contains(*x)) {
+                let message = match get_closest_name(name, &known) {
+                    Some(closest) => format!("unknown type '{}' in {} of {}, did you mean '{}'?", name, usage, func.name, closest),
+                    None => format!("unknown type '{}' in {} of {}", name, usage, func.name)
+                };
+                diagnostics.push(ScarDiagnostic { file: func.source_file.clone(), line: func.source_line, message });
+            }
+        }
+    }
+    diagnostics
+}
+
+/// Checks that every `@see` reference names a function, enum, enum value, global or type of the scardoc.
+///
+/// Should be run on the final scardoc, as references often point to something documented by another scardoc.
+pub fn lint_references(doc: &ScarDoc) -> Vec<ScarDiagnostic> {
+    let functions = doc.categories.iter().flat_map(|x| x.category_functions.iter());
+    let mut known = get_known_types(doc);
+    known.extend(functions.clone().map(|x| x.name.clone()));
+    // Values of enums declared as tables are referred to through their table, like `TEAM_STATE.IDLE`
+    for enum_def in &doc.enums {
+        known.extend(enum_def.values.iter().map(|x| match enum_def.table {
+            true => format!("{}.{}", enum_def.name, x.name),
+            false => x.name.clone()
+        }));
+    }
+    known.extend(doc.globals.iter().map(|x| x.name.clone()));
+
+    let mut diagnostics = Vec::new();
+    for func in functions {
+        for name in func.see.iter().filter(|x| !known.contains(*x)) {
+            let message = match get_closest_name(name, &known) {
+                Some(closest) => format!("dangling reference '{}' in @see of {}, did you mean '{}'?", name, func.name, closest),
+                None => format!("dangling reference '{}' in @see of {}", name, func.name)
+            };
+            diagnostics.push(ScarDiagnostic { file: func.source_file.clone(), line: func.source_line, message });
+        }
+    }
+    diagnostics
+}
+
+/// Runs every lint needing the scar files a scardoc was generated from.
+pub fn lint_scar_files(doc: &ScarDoc, files: &[String]) -> Result<Vec<ScarDiagnostic>, ScarDocError> {
+    let mut diagnostics = lint_signatures(doc, files)?;
+    diagnostics.extend(lint_deprecated_calls(doc, files)?);
+    Ok(diagnostics)
+}
+
+/// Checks that the documented parameters of every function declared in the scar files match its Lua signature.
+///
+/// Functions without documented parameters, and those whose parameter list spans several lines, aren't checked.
+pub fn lint_signatures(doc: &ScarDoc, files: &[String]) -> Result<Vec<ScarDiagnostic>, ScarDocError> {
+    let mut declared: HashMap<&str, Vec<&ScarFunction>> = HashMap::new();
+    for func in doc.categories.iter().flat_map(|x| x.category_functions.iter()).filter(|x| !x.parameters.is_empty()) {
+        if let Some(file) = &func.source_file {
+            declared.entry(file.as_str()).or_default().push(func);
+        }
+    }
+    let mut diagnostics = Vec::new();
+    for file in files {
+        let Some(funcs) = declared.get_mut(file.as_str()) else {
+            continue;
+        };
+        funcs.sort_by_key(|x| x.source_line);
+        let content = fs::read_to_string(file).map_err(|e| ScarDocError::io(file, e))?;
+        let lines: Vec<&str> = content.lines().collect();
+        for func in funcs {
+            let lua_parameters = func.source_line
+                .and_then(|x| lines.get(x.wrapping_sub(1)))
+                .and_then(|x| get_lua_parameters(x));
+            for message in lua_parameters.iter().flat_map(|x| get_signature_mismatches(func, x)) {
+                diagnostics.push(ScarDiagnostic { file: Some(file.clone()), line: func.source_line, message });
+            }
+        }
+    }
+    Ok(diagnostics)
+}
+
+/// Finds the calls to deprecated functions in scar files.
+pub fn lint_deprecated_calls(doc: &ScarDoc, files: &[String]) -> Result<Vec<ScarDiagnostic>, ScarDocError> {
+    let deprecated: HashMap<&str, &ScarDeprecation> = doc.categories.iter()
+        .flat_map(|x| x.category_functions.iter())
+        .filter_map(|x| x.deprecated.as_ref().map(|y| (x.name.as_str(), y)))
+        .collect();
+    let mut diagnostics = Vec::new();
+    if deprecated.is_empty() {
+        return Ok(diagnostics);
+    }
+    for file in files {
+        let content = fs::read_to_string(file).map_err(|e| ScarDocError::io(file, e))?;
+        // Block comments, like the examples of scardoc block comments, and long strings may span several lines
+        let mut long_end: Option<String> = None;
+        for (line_number, ln) in (1..).zip(content.lines()) {
+            for name in get_called_names(ln, &mut long_end) {
+                if let Some(deprecation) = deprecated.get(name) {
+                    let message = format!("call to {}, which is {}", name, deprecation);
+                    diagnostics.push(ScarDiagnostic { file: Some(file.clone()), line: Some(line_number), message });
+                }
+            }
+        }
+    }
+    Ok(diagnostics)
+}
+
+/// Gets the names of the functions called on a line of Lua code, like `Util_ScarPos` or `Player:GetTeam`.
+///
+/// Comments and strings are skipped, as is the name of a function being declared. `long_end` is the closing bracket of
+/// a block comment or long string left open by the previous line, and is set if this line leaves one open.
+fn get_called_names<'a>(ln: &'a str, long_end: &mut Option<String>) -> Vec<&'a str> {
+    let declared = ln.trim_start().strip_prefix("function ").map(|x| x.split('(').next().unwrap_or_default().trim());
+    let mut names = Vec::new();
+    for code in get_code_segments(ln, long_end) {
+        let mut start = None;
+        for (i, c) in code.char_indices() {
+            if c.is_alphanumeric() || matches!(c, '_' | '.' | ':') {
+                start = start.or(Some(i));
+                continue;
+            }
+            if let Some(idx) = start.take() {
+                let is_call = c == '(' || (c.is_whitespace() && code[i..].trim_start().starts_with('('));
+                let name = &code[idx..i];
+                if is_call && Some(name) != declared {
+                    names.push(name);
+                }
+            }
+        }
+    }
+    names
+}
+
+/// Splits a line of Lua code into the parts that aren't comments or strings.
+fn get_code_segments<'a>(ln: &'a str, long_end: &mut Option<String>) -> Vec<&'a str> {
+    let bytes = ln.as_bytes();
+    let mut segments = Vec::new();
+    let mut start = 0;
+    if let Some(end) = long_end.take() {
+        match ln.find(end.as_str()) {
+            Some(idx) => start = idx + end.len(),
+            None => {
+                *long_end = Some(end);
+                return segments;
+            }
+        }
+    }
+    let mut i = start;
+    while i < bytes.len() {
+        let long = match bytes[i] {
+            b'-' if bytes[i..].starts_with(b"--") => match get_long_bracket(&bytes[i + 2..]) {
+                Some((len, end)) => Some((i + 2 + len, end)),
+                None => {
+                    segments.push(&ln[start..i]);
+                    return segments;
+                }
+            },
+            b'[' => get_long_bracket(&bytes[i..]).map(|(len, end)| (i + len, end)),
+            quote @ (b'"' | b'\'') => {
+                segments.push(&ln[start..i]);
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(bytes.len());
+                start = i;
+                continue;
+            }
+            _ => None
+        };
+        match long {
+            None => i += 1,
+            Some((content, end)) => {
+                segments.push(&ln[start..i]);
+                match ln[content..].find(end.as_str()) {
+                    Some(idx) => i = content + idx + end.len(),
+                    None => {
+                        *long_end = Some(end);
+                        return segments;
+                    }
+                }
+                start = i;
+            }
+        }
+    }
+    segments.push(&ln[start..]);
+    segments
+}
+
+/// Gets the length of a long bracket like `[[` or `[==[` at the start of `bytes`, along with its closing bracket.
+fn get_long_bracket(bytes: &[u8]) -> Option<(usize, String)> {
+    let level = bytes.strip_prefix(b"[")?.iter().take_while(|x| **x == b'=').count();
+    (bytes.get(level + 1) == Some(&b'[')).then(|| (level + 2, format!("]{}]", "=".repeat(level))))
+}
+
+/// Gets the names of every type, enum and class (the tables of functions like `Obj.Func` or `Obj:Method`) in the scardoc.
+fn get_known_types(doc: &ScarDoc) -> BTreeSet<String> {
+    let mut known: BTreeSet<String> = get_builtin_types().into_iter().map(|x| x.name).collect();
+    known.extend(doc.types.iter().map(|x| x.name.clone()));
+    known.extend(doc.enums.iter().map(|x| x.name.clone()));
+    for func in doc.categories.iter().flat_map(|x| x.category_functions.iter()) {
+        if let Some(idx) = func.name.find(['.', ':']) {
+            known.insert(func.name[..idx].to_string());
+        }
+    }
+    known
+}
+
+/// Splits a type like `Real|nil`, `SGroupID[]` or `table<String, Position>` into the names of the types it consists of.
+///
+/// Nil, function types like `fun(x: Real): Boolean` and table literals like `{ x: Real }` are left out, and the
+/// `table` of generic tables is a Lua table.
+fn get_type_names(scar_type: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in scar_type.char_indices() {
+        match c {
+            '<' | '(' | '{' | '[' => depth += 1,
+            '>' | ')' | '}' | ']' => depth -= 1,
+            '|' | '/' | ',' if depth == 0 => {
+                parts.push(&scar_type[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&scar_type[start..]);
+
+    let mut names = Vec::new();
+    for part in parts {
+        let mut part = part.trim();
+        while let Some(element) = part.strip_suffix("[]") {
+            part = element.trim_end();
+        }
+        if part.is_empty() || part.eq_ignore_ascii_case("nil") || part.starts_with("fun(") || part.starts_with('{') {
+            continue;
+        }
+        if let Some(inner) = part.strip_prefix('(').and_then(|x| x.strip_suffix(')')) {
+            names.extend(get_type_names(inner));
+            continue;
+        }
+        match part.split_once('<') {
+            Some((container, args)) => {
+                let container = container.trim();
+                if container != "table" {
+                    names.push(container);
+                }
+                names.extend(get_type_names(args.strip_suffix('>').unwrap_or(args)));
+            }
+            None => names.push(part)
+        }
+    }
+    names
+}
+
+/// Gets the known name closest to `name`, ignoring case, if it's close enough to be a typo.
+fn get_closest_name<'a>(name: &str, known: &'a BTreeSet<String>) -> Option<&'a str> {
+    let name = name.to_lowercase();
+    let max_distance = (name.chars().count() / 3).max(1);
+    known.iter()
+        .map(|x| (get_edit_distance(&name, &x.to_lowercase()), x))
+        .filter(|x| x.0 <= max_distance)
+        .min_by_key(|x| x.0)
+        .map(|x| x.1.as_str())
+}
+
+/// Gets the Levenshtein distance between two strings.
+fn get_edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+mod tests {
+
+    #[test]
+    fn can_get_edit_distance() {
+        assert_eq!(super::get_edit_distance("position", "position"), 0);
+        assert_eq!(super::get_edit_distance("postion", "position"), 1);
+        assert_eq!(super::get_edit_distance("kitten", "sitting"), 3);
+        assert_eq!(super::get_edit_distance("", "real"), 4);
+    }
+
+    #[test]
+    fn can_get_called_names() {
+        let get_called_names = |ln| super::get_called_names(ln, &mut None);
+        assert_eq!(get_called_names("\tlocal pos = Util_ScarPos(1, World_GetHeightAt(1, 2)) -- Util_Old()"), vec!["Util_ScarPos", "World_GetHeightAt"]);
+        assert_eq!(get_called_names("function Util_Old (x) return player:GetTeam () end"), vec!["player:GetTeam"]);
+        assert!(get_called_names("if x == nil then").is_empty());
+        assert_eq!(get_called_names("print(\"Util_Old() isn't called\", 'Util_Old(\\'x\\')', [[Util_Old()]])"), vec!["print"]);
+        assert_eq!(get_called_names("local x = t[1] --[[ Util_Old() ]] Util_ScarPos()"), vec!["Util_ScarPos"]);
+
+        // Block comments and long strings spanning several lines are skipped until they're closed
+        let mut long_end = None;
+        assert_eq!(super::get_called_names("Util_ScarPos() --[==[ Util_Old()", &mut long_end), vec!["Util_ScarPos"]);
+        assert!(super::get_called_names("Util_Old() ]]", &mut long_end).is_empty());
+        assert_eq!(super::get_called_names("]==] Util_ScarPos()", &mut long_end), vec!["Util_ScarPos"]);
+        assert!(long_end.is_none());
+    }
+
+    #[test]
+    fn can_lint_signatures() {
+        let content = "\
+            --? @args Real xpos, Real zpos[, Real ypos]\n\
+            function Util_ScarPos(xpos, zpos, ypos)\n\
+            \n\
+            --? @args Real x, Real z\n\
+            function Util_ScarPos2(xpos, z)\n\
+            \n\
+            --? @args String, Integer\n\
+            function Util_Unnamed(name, count, ...)\n\
+            \n\
+            --? @args Real x[, ...]\n\
+            function Util_Vararg(x)\n\
+            \n\
+            ---@param name string\n\
+            function Util_Param(name, pos)\n\
+            \n\
+            --? @shortdesc Has no documented parameters\n\
+            function Util_Undocumented(a, b)\n";
+        let dir = std::env::temp_dir().join(format!("scardoc-lint-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("signatures.scar"), content).unwrap();
+        let doc = crate::scardoc::generate_scardoc(&dir).unwrap();
+        let files = crate::scardoc::get_scar_files(&dir).unwrap();
+
+        // Mismatches are found in the scar files rather than while parsing, so they're reported for cached files too
+        let diagnostics: Vec<(Option<usize>, String)> = super::lint_signatures(&doc, &files).unwrap().into_iter()
+            .map(|x| (x.line, x.message))
+            .collect();
+        assert_eq!(diagnostics, vec![
+            (Some(5), String::from("parameter 1 of Util_ScarPos2 is named 'xpos' but documented as 'x'")),
+            (Some(8), String::from("Util_Unnamed takes varargs but doesn't document them")),
+            (Some(11), String::from("Util_Vararg documents varargs but doesn't take them")),
+            (Some(14), String::from("Util_Param takes 2 parameters but documents 1"))
+        ]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn can_lint_unnamed_optional_signatures() {
+        let content = "\
+            --? @args Real[, Real]\n\
+            function Util_Offset(x, z)\n\
+            \n\
+            --? @args Real, Real[, Real]\n\
+            function Util_Offset3(x, z)\n";
+        let dir = std::env::temp_dir().join(format!("scardoc-lint-unnamed-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("unnamed.scar"), content).unwrap();
+        let doc = crate::scardoc::generate_scardoc(&dir).unwrap();
+        let files = crate::scardoc::get_scar_files(&dir).unwrap();
+
+        // Unnamed arguments are numbered across the mandatory and optional sections
+        let diagnostics: Vec<(Option<usize>, String)> = super::lint_signatures(&doc, &files).unwrap().into_iter()
+            .map(|x| (x.line, x.message))
+            .collect();
+        assert_eq!(diagnostics, vec![(Some(5), String::from("Util_Offset3 takes 2 parameters but documents 3"))]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn can_lint_deprecated_calls() {
+        let mut doc = crate::scardoc::generate_scardoc("scar").unwrap();
+        let files = vec![String::from("scar/simple.scar")];
+        assert!(super::lint_deprecated_calls(&doc, &files).unwrap().is_empty());
+
+        let func = doc.get_function_mut("Util_ScarPos").unwrap();
+        func.deprecated = Some(crate::scarfile::get_deprecation("World_Pos"));
+
+        // Deprecated functions are reported where they're called rather than declared
+        let mut callee = func.clone();
+        callee.name = String::from("World_GetHeightAt");
+        callee.deprecated = Some(crate::scarfile::get_deprecation("Heights are always 0"));
+        doc.categories[0].category_functions.push(callee);
+        let diagnostics: Vec<String> = super::lint_deprecated_calls(&doc, &files).unwrap().iter().map(|x| x.to_string()).collect();
+        assert_eq!(diagnostics, vec!["scar/simple.scar:10: call to World_GetHeightAt, which is deprecated: Heights are always 0"]);
+    }
+
+    #[test]
+    fn can_lint_references() {
+        let mut doc = crate::scardoc::generate_scardoc("scar").unwrap();
+        let func = doc.get_function_mut("Util_ScarPos").unwrap();
+        func.see = vec![String::from("Util_SpawnGroup"), String::from("Position"), String::from("Util_SpawnGrop"), String::from("World_Pos"),
+            String::from("TEAM_STATE.IDLE"), String::from("IDLE")];
+        let diagnostics: Vec<String> = super::lint_references(&doc).iter().map(|x| x.message.clone()).collect();
+        assert_eq!(diagnostics, vec![
+            "dangling reference 'Util_SpawnGrop' in @see of Util_ScarPos, did you mean 'Util_SpawnGroup'?",
+            "dangling reference 'World_Pos' in @see of Util_ScarPos",
+            "dangling reference 'IDLE' in @see of Util_ScarPos"
+        ]);
+    }
+
+    #[test]
+    fn can_lint_types() {
+        assert_eq!(super::get_type_names("SGroupID[]|nil"), vec!["SGroupID"]);
+        assert_eq!(super::get_type_names("table<String, (Real|Position)[]>"), vec!["String", "Real", "Position"]);
+        assert_eq!(super::get_type_names("fun(x: Real): Boolean|{ x: Real }"), Vec::<&str>::new());
+        let mut doc = crate::scardoc::generate_scardoc("scar").unwrap();
+        let diagnostics: Vec<String> = super::lint_types(&doc).iter().map(|x| x.to_string()).collect();
+        // Engine types such as SGroupID are built in
+        assert!(diagnostics.is_empty());
+        doc.get_function_mut("Util_SpawnGroup").unwrap().return_type = Some(String::from("SGroupHandle"));
+        let diagnostics: Vec<String> = super::lint_types(&doc).iter().map(|x| x.to_string()).collect();
+        assert_eq!(diagnostics, vec!["scar/emmylua.scar:8: unknown type 'SGroupHandle' in return type of Util_SpawnGroup"]);
+
+        let func = doc.get_function_mut("Util_ScarPos").unwrap();
+        func.parameters[0].arg_type = String::from("Postion|nil");
+        func.parameters[1].arg_type = String::from("Squad");
+        func.parameters[2].arg_type = String::from("Position[]|table<String, Postion[]>|fun(x: Real): Boolean");
+        func.return_type = Some(String::from("integer"));
+        let diagnostics: Vec<String> = super::lint_types(&doc).into_iter()
+            .filter(|x| x.file.as_deref() == Some("scar/simple.scar"))
+            .map(|x| x.message)
+            .collect();
+        assert_eq!(diagnostics, vec![
+            "unknown type 'Postion' in parameter 'xpos' of Util_ScarPos, did you mean 'Position'?",
+            "unknown type 'Squad' in parameter 'zpos' of Util_ScarPos",
+            "unknown type 'Postion' in parameter 'ypos' of Util_ScarPos, did you mean 'Position'?",
+            "unknown type 'integer' in return type of Util_ScarPos, did you mean 'Integer'?"
+        ]);
+    }
+
+    #[test]
+    fn can_lint_nested_generic_types() {
+        assert_eq!(super::get_type_names("table<String, table<Integer, SGroupID[]>>|nil"), vec!["String", "Integer", "SGroupID"]);
+        let mut doc = crate::scardoc::generate_scardoc("scar").unwrap();
+        doc.get_function_mut("Util_ScarPos").unwrap().return_type = Some(String::from("table<String, table<PlayerID, Postion>>"));
+        let diagnostics: Vec<String> = super::lint_types(&doc).into_iter().map(|x| x.message).collect();
+        assert_eq!(diagnostics, vec!["unknown type 'Postion' in return type of Util_ScarPos, did you mean 'Position'?"]);
+    }
+
+}
@@ -0,0 +1,709 @@
+use std::{collections::HashMap, fs, io::{self, BufRead, Write}, path::Path};
+use serde_json::{json, Value};
+
+use crate::{scarerror::ScarDocError, scardoc::{ScarDoc, ScarGlobal, get_filtered_scar_files, read_scar_files}, scarfilter::{ScarFileFilter, ScarFileMatcher, SCARDOC_IGNORE_FILE}, scarfile::{ScarFunction, ScarSourceFile, ScarDeprecation, get_scar_sourcefile}, scarenum::{ScarEnum, ScarEnumValue}};
+
+const LSP_INVALID_REQUEST: i64 = -32600;
+const LSP_METHOD_NOT_FOUND: i64 = -32601;
+
+const LSP_COMPLETION_FUNCTION: i64 = 3;
+const LSP_COMPLETION_VARIABLE: i64 = 6;
+const LSP_COMPLETION_ENUM_MEMBER: i64 = 20;
+
+const LSP_COMPLETION_TAG_DEPRECATED: i64 = 1;
+
+/// A language server providing completion, hover, signature help and go-to-definition for scar code.
+pub struct ScarLanguageServer {
+    functions: HashMap<String, ScarFunction>,
+    globals: HashMap<String, ScarGlobal>,
+    enum_values: HashMap<String, (String, ScarEnumValue, Option<ScarDeprecation>)>,
+    /// The names of the functions, globals and enum values defined by each scar file, by canonical path.
+    file_names: HashMap<String, Vec<String>>,
+    documents: HashMap<String, String>,
+    scar_dir: Option<String>,
+    /// Which files of the scar directory are read, like when generating a scardoc.
+    filter: ScarFileFilter,
+    matcher: Option<ScarFileMatcher>,
+    can_watch_files: bool,
+    is_shutdown: bool
+}
+
+impl ScarLanguageServer {
+
+    pub fn new(doc: ScarDoc) -> Self {
+        let mut server = ScarLanguageServer {
+            functions: HashMap::new(),
+            globals: HashMap::new(),
+            enum_values: HashMap::new(),
+            file_names: HashMap::new(),
+            documents: HashMap::new(),
+            scar_dir: None,
+            filter: ScarFileFilter::default(),
+            matcher: None,
+            can_watch_files: false,
+            is_shutdown: false
+        };
+        for func in doc.categories.into_iter().flat_map(|x| x.category_functions.into_iter()) {
+            server.functions.insert(func.name.clone(), func);
+        }
+        for global in doc.globals {
+            server.globals.insert(global.name.clone(), global);
+        }
+        for enum_def in doc.enums {
+            server.add_enum(enum_def);
+        }
+        server
+    }
+
+    /// Parses every scar file in `dir_path` selected by `filter` and keeps them up to date when they're saved.
+    pub fn watch_directory(&mut self, dir_path: String, filter: &ScarFileFilter) {
+        self.scar_dir = Some(dir_path);
+        self.filter = filter.clone();
+        self.load_directory();
+    }
+
+    /// Parses the scar files of the watched directory, replacing what was read from it before.
+    fn load_directory(&mut self) {
+        let Some(dir_path) = self.scar_dir.clone() else {
+            return;
+        };
+        for file_key in self.file_names.keys().cloned().collect::<Vec<String>>() {
+            self.remove_scar_file(&file_key);
+        }
+        // The .scardocignore file may be edited while the server runs, so the matcher is rebuilt every time
+        self.matcher = match self.filter.get_matcher(&dir_path) {
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+            Ok(matcher) => Some(matcher)
+        };
+        match get_filtered_scar_files(&dir_path, &self.filter) {
+            Err(e) => eprintln!("{}", e),
+            Ok(files) => {
+                for src in read_scar_files(&files, 0).into_iter().filter_map(|x| x.ok()) {
+                    self.add_scar_file(src);
+                }
+            }
+        }
+    }
+
+    fn load_scar_file(&mut self, file_path: &str) {
+        match get_scar_sourcefile(file_path.to_string()) {
+            Err(e) => eprintln!("Failed reading scar file with error: {}", e),
+            Ok(src) => self.add_scar_file(src)
+        }
+    }
+
+    fn add_scar_file(&mut self, src: ScarSourceFile) {
+        // What the file defined before is replaced, so functions removed from it are forgotten
+        let file_key = get_file_key(&src.source_name);
+        self.remove_scar_file(&file_key);
+        let mut names = Vec::new();
+        for func in src.functions {
+            names.push(func.name.clone());
+            self.functions.insert(func.name.clone(), func);
+        }
+        for global in src.globals {
+            names.push(global.name.clone());
+            self.globals.insert(global.name.clone(), global);
+        }
+        for enum_def in src.enums {
+            names.extend(self.add_enum(enum_def));
+        }
+        self.file_names.insert(file_key, names);
+    }
+
+    fn remove_scar_file(&mut self, file_key: &str) {
+        for name in self.file_names.remove(file_key).into_iter().flatten() {
+            self.functions.remove(&name);
+            self.globals.remove(&name);
+            self.enum_values.remove(&name);
+        }
+    }
+
+    /// Adds the values of an enum, returning the names they're referred to by.
+    fn add_enum(&mut self, enum_def: ScarEnum) -> Vec<String> {
+        let mut names = Vec::new();
+        for value in enum_def.values {
+            // Values of enums declared as tables are referred to through the table
+            let name = match enum_def.table {
+                true => format!("{}.{}", enum_def.name, value.name),
+                false => value.name.clone()
+            };
+            names.push(name.clone());
+            self.enum_values.insert(name, (enum_def.name.clone(), value, enum_def.deprecated.clone()));
+        }
+        names
+    }
+
+    /// Serves the language server protocol over stdin/stdout until the client asks the server to exit.
+    pub fn run(&mut self) -> Result<(), ScarDocError> {
+        let stdin = io::stdin();
+        let mut reader = stdin.lock();
+        let stdout = io::stdout();
+        let mut writer = stdout.lock();
+        while let Some(msg) = read_message(&mut reader)? {
+            if msg["method"] == "exit" {
+                break;
+            }
+            if let Some(response) = self.handle_message(&msg) {
+                write_message(&mut writer, &response)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_message(&mut self, msg: &Value) -> Option<Value> {
+        let Some(method) = msg["method"].as_str() else {
+            // Responses to the requests sent by the server aren't used
+            return None;
+        };
+        let params = &msg["params"];
+        let id = msg.get("id").cloned().unwrap_or(Value::Null);
+
+        // Notifications have no id and never get a response, but may make the server send a request
+        if id.is_null() {
+            return self.handle_notification(method, params);
+        }
+
+        if self.is_shutdown {
+            return Some(json!({ "jsonrpc": "2.0", "id": id, "error": {
+                "code": LSP_INVALID_REQUEST,
+                "message": "Server has been shut down"
+            }}));
+        }
+
+        let result = match method {
+            "initialize" => {
+                self.can_watch_files = params["capabilities"]["workspace"]["didChangeWatchedFiles"]["dynamicRegistration"] == true;
+                Some(get_capabilities())
+            }
+            "shutdown" => {
+                self.is_shutdown = true;
+                Some(Value::Null)
+            }
+            "textDocument/completion" => Some(self.get_completion(params)),
+            "textDocument/hover" => Some(self.get_hover(params)),
+            "textDocument/signatureHelp" => Some(self.get_signature_help(params)),
+            "textDocument/definition" => Some(self.get_definition(params)),
+            _ => None
+        };
+
+        Some(match result {
+            Some(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            None => json!({ "jsonrpc": "2.0", "id": id, "error": {
+                "code": LSP_METHOD_NOT_FOUND,
+                "message": format!("Unsupported method '{}'", method)
+            }})
+        })
+    }
+
+    fn handle_notification(&mut self, method: &str, params: &Value) -> Option<Value> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default().to_string();
+        match method {
+            // Scar files changed outside of the editor are only reported if the server asks to watch them
+            "initialized" if self.can_watch_files && self.scar_dir.is_some() => {
+                return Some(json!({ "jsonrpc": "2.0", "id": "watch-scar-files", "method": "client/registerCapability", "params": {
+                    "registrations": [{
+                        "id": "watch-scar-files",
+                        "method": "workspace/didChangeWatchedFiles",
+                        "registerOptions": { "watchers": self.get_file_watchers() }
+                    }]
+                }}));
+            }
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri, text.to_string());
+            }
+            "textDocument/didChange" => {
+                // We only ask for full document syncs, so the last change holds the whole document
+                if let Some(text) = params["contentChanges"].as_array().and_then(|x| x.last()).and_then(|x| x["text"].as_str()) {
+                    self.documents.insert(uri, text.to_string());
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+            }
+            "textDocument/didSave" => self.reload_uri(&uri),
+            "workspace/didChangeWatchedFiles" => {
+                let uris: Vec<String> = params["changes"].as_array().into_iter()
+                    .flatten()
+                    .filter_map(|x| x["uri"].as_str())
+                    .map(|x| x.to_string())
+                    .collect();
+                for uri in uris {
+                    self.reload_uri(&uri);
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Gets the glob patterns of the files read from the scar directory, along with its `.scardocignore` file.
+    fn get_file_watchers(&self) -> Vec<Value> {
+        let extensions = ["scar"].into_iter().chain(self.filter.extensions.iter().map(|x| x.trim_start_matches('.')));
+        extensions.map(|x| format!("**/*.{}", x))
+            .chain([format!("**/{}", SCARDOC_IGNORE_FILE)])
+            .map(|x| json!({ "globPattern": x }))
+            .collect()
+    }
+
+    /// Rereads a saved scar file of the watched directory, or forgets what it defined if it was deleted.
+    ///
+    /// Files skipped by the filter are ignored, and changing the `.scardocignore` file rereads the whole directory.
+    fn reload_uri(&mut self, uri: &str) {
+        let Some(scar_dir) = self.scar_dir.as_deref() else {
+            return;
+        };
+        let file_path = get_file_path(uri);
+        let file_key = get_file_key(&file_path);
+        let Ok(relative_path) = Path::new(&file_key).strip_prefix(get_file_key(scar_dir)) else {
+            return;
+        };
+        if relative_path == Path::new(SCARDOC_IGNORE_FILE) {
+            self.load_directory();
+        } else if !Path::new(&file_path).exists() {
+            self.remove_scar_file(&file_key);
+        } else if self.matcher.as_ref().is_some_and(|x| x.is_match(relative_path)) {
+            self.load_scar_file(&file_path);
+        }
+    }
+
+    /// Gets the line at the position of a request, along with the character index of the position.
+    fn get_line(&self, params: &Value) -> Option<(String, usize)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let line = params["position"]["line"].as_u64()? as usize;
+        let character = params["position"]["character"].as_u64()? as usize;
+        let text = self.documents.get(uri)?;
+        let line = text.lines().nth(line).unwrap_or_default().to_string();
+        let character = get_char_index(&line, character);
+        Some((line, character))
+    }
+
+    fn get_completion(&self, params: &Value) -> Value {
+        let Some((line, character)) = self.get_line(params) else {
+            return json!([]);
+        };
+        let prefix = get_word_before(&line, character);
+        let mut items = Vec::new();
+        for func in self.functions.values().filter(|x| x.name.starts_with(&prefix)) {
+            items.push(json!({
+                "label": func.name,
+                "kind": LSP_COMPLETION_FUNCTION,
+                "detail": get_signature(func),
+                "documentation": { "kind": "markdown", "value": self.get_function_markdown(func) },
+                "tags": get_completion_tags(func.deprecated.as_ref())
+            }));
+        }
+        for global in self.globals.values().filter(|x| x.name.starts_with(&prefix)) {
+            items.push(json!({
+                "label": global.name,
+                "kind": LSP_COMPLETION_VARIABLE,
+                "detail": global.value.clone().unwrap_or_default(),
+                "documentation": global.description.clone().unwrap_or_default(),
+                "tags": get_completion_tags(global.deprecated.as_ref())
+            }));
+        }
+        for (name, (enum_name, value, deprecated)) in self.enum_values.iter().filter(|x| x.0.starts_with(&prefix)) {
+            items.push(json!({
+                "label": name,
+                "kind": LSP_COMPLETION_ENUM_MEMBER,
+                "detail": enum_name,
+                "documentation": value.description.clone().unwrap_or_default(),
+                "tags": get_completion_tags(value.deprecated.as_ref().or(deprecated.as_ref()))
+            }));
+        }
+        json!({ "isIncomplete": false, "items": items })
+    }
+
+    fn get_hover(&self, params: &Value) -> Value {
+        let Some((line, character)) = self.get_line(params) else {
+            return Value::Null;
+        };
+        let word = get_word_at(&line, character);
+        let content = if let Some(func) = self.functions.get(&word) {
+            self.get_function_markdown(func)
+        } else if let Some(global) = self.globals.get(&word) {
+            let mut content = format!("```lua\n{} = {}\n```", global.name, global.value.clone().unwrap_or_else(|| String::from("nil")));
+            if let Some(desc) = &global.description {
+                content.push_str(&format!("\n\n{}", desc));
+            }
+            content.push_str(&get_notices(&global.name, global.deprecated.as_ref(), global.since.as_deref()));
+            content
+        } else if let Some((enum_name, value, deprecated)) = self.enum_values.get(&word) {
+            let mut content = format!("```lua\n{}: {} = {}\n```", word, enum_name, value.value.clone().unwrap_or_else(|| String::from("?")));
+            if let Some(desc) = &value.description {
+                content.push_str(&format!("\n\n{}", desc));
+            }
+            match &value.deprecated {
+                Some(value_deprecated) => content + &get_notices(&word, Some(value_deprecated), None),
+                None => content + &get_notices(enum_name, deprecated.as_ref(), None)
+            }
+        } else {
+            return Value::Null;
+        };
+        json!({ "contents": { "kind": "markdown", "value": content } })
+    }
+
+    fn get_signature_help(&self, params: &Value) -> Value {
+        let Some((line, character)) = self.get_line(params) else {
+            return Value::Null;
+        };
+        let Some((name, active_parameter)) = get_call_at(&line, character) else {
+            return Value::Null;
+        };
+        let Some(func) = self.functions.get(&name) else {
+            return Value::Null;
+        };
+        let parameters: Vec<Value> = func.parameters.iter()
+            .map(|x| json!({ "label": get_parameter_label(x), "documentation": x.arg_description.clone().unwrap_or_default() }))
+            .collect();
+        json!({
+            "signatures": [{
+                "label": get_signature(func),
+                "documentation": { "kind": "markdown", "value": get_function_description(func) },
+                "parameters": parameters
+            }],
+            "activeSignature": 0,
+            "activeParameter": active_parameter
+        })
+    }
+
+    fn get_definition(&self, params: &Value) -> Value {
+        let Some((line, character)) = self.get_line(params) else {
+            return Value::Null;
+        };
+        let Some((source_file, source_line)) = self.get_source_location(&get_word_at(&line, character)) else {
+            return Value::Null;
+        };
+        let line = source_line.saturating_sub(1);
+        json!({
+            "uri": get_file_uri(source_file),
+            "range": {
+                "start": { "line": line, "character": 0 },
+                "end": { "line": line, "character": 0 }
+            }
+        })
+    }
+
+    /// Gets the file and line a function or enum value is declared at, if it was read from a scar file.
+    fn get_source_location(&self, name: &str) -> Option<(&str, usize)> {
+        let (source_file, source_line) = match (self.functions.get(name), self.enum_values.get(name)) {
+            (Some(func), _) => (&func.source_file, func.source_line),
+            (None, Some((_, value, _))) => (&value.source_file, value.source_line),
+            (None, None) => return None
+        };
+        Some((source_file.as_deref()?, source_line.unwrap_or(1)))
+    }
+
+    /// Describes a function, with its `@see` references linking to where they're declared when known.
+    fn get_function_markdown(&self, func: &ScarFunction) -> String {
+        let mut notices = get_notices(&func.name, func.deprecated.as_ref(), func.since.as_deref());
+        if !func.see.is_empty() {
+            let names: Vec<String> = func.see.iter()
+                .map(|x| match self.get_source_location(x) {
+                    Some((file, line)) => format!("[`{}`]({}#L{})", x, get_file_uri(file), line),
+                    None => format!("`{}`", x)
+                })
+                .collect();
+            notices.push_str(&format!("\n\nSee also: {}", names.join(", ")));
+        }
+        format!("```lua\n{}\n```\n\n{}{}", get_signature(func), get_function_description(func), notices)
+    }
+
+}
+
+fn get_capabilities() -> Value {
+    json!({
+        "capabilities": {
+            "textDocumentSync": { "openClose": true, "change": 1, "save": true },
+            "completionProvider": { "triggerCharacters": [".", ":", "_"] },
+            "hoverProvider": true,
+            "signatureHelpProvider": { "triggerCharacters": ["(", ","] },
+            "definitionProvider": true
+        },
+        "serverInfo": { "name": "coh3-scardoc-gen", "version": env!("CARGO_PKG_VERSION") }
+    })
+}
+
+fn read_message(reader: &mut impl BufRead) -> Result<Option<Value>, ScarDocError> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).map_err(|e| ScarDocError::io("stdin", e))? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((key, value)) = header.split_once(':') {
+            if key.eq_ignore_ascii_case("Content-Length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let content_length = content_length.ok_or_else(|| {
+        ScarDocError::io("stdin", io::Error::new(io::ErrorKind::InvalidData, "message is missing a valid Content-Length header"))
+    })?;
+    let mut content = vec![0; content_length];
+    reader.read_exact(&mut content).map_err(|e| ScarDocError::io("stdin", e))?;
+    serde_json::from_slice(&content).map(Some).map_err(|e| ScarDocError::json("stdin", e))
+}
+
+fn write_message(writer: &mut impl Write, msg: &Value) -> Result<(), ScarDocError> {
+    let content = serde_json::to_string(msg).map_err(|e| ScarDocError::json("stdout", e))?;
+    write!(writer, "Content-Length: {}\r\n\r\n{}", content.len(), content)
+        .and_then(|_| writer.flush())
+        .map_err(|e| ScarDocError::io("stdout", e))
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == ':'
+}
+
+fn get_word_before(line: &str, character: usize) -> String {
+    let chars: Vec<char> = line.chars().take(character).collect();
+    let start = chars.iter().rposition(|x| !is_word_char(*x)).map_or(0, |x| x + 1);
+    chars[start..].iter().collect()
+}
+
+fn get_word_at(line: &str, character: usize) -> String {
+    let after: String = line.chars().skip(character).take_while(|x| is_word_char(*x)).collect();
+    get_word_before(line, character) + &after
+}
+
+/// Finds the function call surrounding `character` and the index of the argument being written.
+fn get_call_at(line: &str, character: usize) -> Option<(String, usize)> {
+    let chars: Vec<char> = line.chars().take(character).collect();
+    let mut depth = 0;
+    let mut active_parameter = 0;
+    for (i, c) in chars.iter().enumerate().rev() {
+        match c {
+            ')' | '}' => depth += 1,
+            '(' | '{' if depth > 0 => depth -= 1,
+            '{' => return None,
+            '(' => {
+                let name = get_word_before(line, i);
+                return if name.is_empty() { None } else { Some((name, active_parameter)) };
+            }
+            ',' if depth == 0 => active_parameter += 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn get_parameter_label(param: &crate::scarfile::ScarParameter) -> String {
+    let label = match &param.arg_default {
+        Some(value) => format!("{} {} = {}", param.arg_type, param.arg_name, value),
+        None => format!("{} {}", param.arg_type, param.arg_name)
+    };
+    if param.arg_required { label } else { format!("[{}]", label) }
+}
+
+fn get_signature(func: &ScarFunction) -> String {
+    let params: Vec<String> = func.parameters.iter().map(get_parameter_label).collect();
+    match &func.return_type {
+        Some(ty) => format!("{}({}) -> {}", func.name, params.join(", "), ty),
+        None => format!("{}({})", func.name, params.join(", "))
+    }
+}
+
+fn get_function_description(func: &ScarFunction) -> String {
+    let mut content = func.description_short.clone().unwrap_or_default();
+    if !func.description_extended.is_empty() {
+        content.push_str("\n\n");
+        content.push_str(&func.description_extended.join("\n"));
+    }
+    content
+}
+
+/// Describes the version something was introduced in and whether it's deprecated, with its name struck through.
+fn get_notices(name: &str, deprecated: Option<&ScarDeprecation>, since: Option<&str>) -> String {
+    let mut content = String::new();
+    if let Some(deprecated) = deprecated {
+        content.push_str(&format!("\n\n~~{}~~ is {}", name, deprecated));
+    }
+    if let Some(since) = since {
+        content.push_str(&format!("\n\n*Since {}*", since));
+    }
+    content
+}
+
+fn get_completion_tags(deprecated: Option<&ScarDeprecation>) -> Vec<i64> {
+    deprecated.map(|_| LSP_COMPLETION_TAG_DEPRECATED).into_iter().collect()
+}
+
+/// Converts a position counted in UTF-16 code units, as positions are in the language server protocol, to a character index.
+fn get_char_index(line: &str, utf16_offset: usize) -> usize {
+    let mut offset = 0;
+    line.chars().take_while(|x| {
+        offset += x.len_utf16();
+        offset <= utf16_offset
+    }).count()
+}
+
+/// Gets the canonical path of a file, so paths given by the editor and read from the scar directory can be compared.
+///
+/// The directory of a deleted file is canonicalized instead.
+fn get_file_key(file_path: &str) -> String {
+    let path = Path::new(file_path);
+    let canonical = fs::canonicalize(path).or_else(|e| match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) => fs::canonicalize(if dir.as_os_str().is_empty() { Path::new(".") } else { dir }).map(|x| x.join(name)),
+        _ => Err(e)
+    });
+    canonical.map(|x| x.to_string_lossy().to_string()).unwrap_or(file_path.to_string())
+}
+
+fn get_file_uri(file_path: &str) -> String {
+    let path = std::path::absolute(file_path).map(|x| x.to_string_lossy().to_string()).unwrap_or(file_path.to_string());
+    let path = path.replace('\\', "/").replace(' ', "%20");
+    if path.starts_with('/') {
+        format!("file://{}", path)
+    } else {
+        format!("file:///{}", path)
+    }
+}
+
+fn get_file_path(uri: &str) -> String {
+    let path = uri.strip_prefix("file://").unwrap_or(uri).replace("%20", " ").replace("%3A", ":");
+    // Windows paths are given as 'file:///C:/...'
+    match path.strip_prefix('/') {
+        Some(p) if p.chars().nth(1) == Some(':') => p.to_string(),
+        _ => path
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn can_get_call_at() {
+        let line = "\tUtil_ScarPos(10, World_GetHeightAt(1, 2), ";
+        assert_eq!(super::get_call_at(line, line.len()), Some((String::from("Util_ScarPos"), 2)));
+        assert_eq!(super::get_call_at(line, 38), Some((String::from("World_GetHeightAt"), 1)));
+        assert_eq!(super::get_call_at("local x = 5", 11), None);
+    }
+
+    #[test]
+    fn can_get_char_index() {
+        // '💥' is two UTF-16 code units but a single character
+        let line = "local s = \"💥\" .. Util_ScarPos(";
+        assert_eq!(super::get_char_index(line, 14), 13);
+        assert_eq!(super::get_char_index(line, 100), line.chars().count());
+        assert_eq!(super::get_word_at(line, super::get_char_index(line, 20)), "Util_ScarPos");
+    }
+
+    #[test]
+    fn can_reload_scar_files() {
+        let scar_dir = std::env::temp_dir().join("scardoc-lsp-reload");
+        let _ = std::fs::remove_dir_all(&scar_dir);
+        std::fs::create_dir_all(&scar_dir).unwrap();
+        let scar_file = scar_dir.join("mission.scar");
+        std::fs::write(&scar_file, "--? @shortdesc Starts.\nfunction Mission_Start()\nend\n--? @shortdesc Stops.\nfunction Mission_Stop()\nend\n").unwrap();
+
+        let mut server = super::ScarLanguageServer::new(crate::scardoc::ScarDoc::default());
+        server.watch_directory(scar_dir.display().to_string(), &crate::scarfilter::ScarFileFilter::default());
+        assert!(server.functions.contains_key("Mission_Stop"));
+
+        // Files are only watched once the client supports it
+        server.handle_message(&serde_json::json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {
+            "capabilities": { "workspace": { "didChangeWatchedFiles": { "dynamicRegistration": true } } }
+        }}));
+        let request = server.handle_message(&serde_json::json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} })).unwrap();
+        assert_eq!(request["method"], "client/registerCapability");
+        assert_eq!(request["params"]["registrations"][0]["registerOptions"]["watchers"], serde_json::json!([
+            { "globPattern": "**/*.scar" }, { "globPattern": "**/.scardocignore" }
+        ]));
+        assert!(server.handle_message(&serde_json::json!({ "jsonrpc": "2.0", "id": "watch-scar-files", "result": null })).is_none());
+
+        // Functions removed from a saved file are forgotten
+        std::fs::write(&scar_file, "--? @shortdesc Starts.\nfunction Mission_Start()\nend\n").unwrap();
+        let uri = super::get_file_uri(&scar_file.display().to_string());
+        server.handle_message(&serde_json::json!({ "jsonrpc": "2.0", "method": "textDocument/didSave", "params": { "textDocument": { "uri": uri } } }));
+        assert!(server.functions.contains_key("Mission_Start"));
+        assert!(!server.functions.contains_key("Mission_Stop"));
+
+        // And so is everything defined by a deleted file
+        std::fs::remove_file(&scar_file).unwrap();
+        server.handle_message(&serde_json::json!({ "jsonrpc": "2.0", "method": "workspace/didChangeWatchedFiles", "params": { "changes": [{ "uri": uri, "type": 3 }] } }));
+        assert!(server.functions.is_empty());
+
+        // Files skipped by the filter or .scardocignore aren't read, until the ignore file changes
+        let save_file = |server: &mut super::ScarLanguageServer, file: &std::path::Path| {
+            let uri = super::get_file_uri(&file.display().to_string());
+            server.handle_message(&serde_json::json!({ "jsonrpc": "2.0", "method": "textDocument/didSave", "params": { "textDocument": { "uri": uri } } }));
+        };
+        let ignore_file = scar_dir.join(crate::scarfilter::SCARDOC_IGNORE_FILE);
+        std::fs::create_dir_all(scar_dir.join("_old")).unwrap();
+        std::fs::write(&ignore_file, "_old/\n").unwrap();
+        std::fs::write(scar_dir.join("_old").join("old.scar"), "--? @shortdesc Old.\nfunction Old()\nend\n").unwrap();
+        std::fs::write(scar_dir.join("ui.lua"), "--? @shortdesc Menu.\nfunction Menu()\nend\n").unwrap();
+        save_file(&mut server, &ignore_file);
+        save_file(&mut server, &scar_dir.join("_old").join("old.scar"));
+        save_file(&mut server, &scar_dir.join("ui.lua"));
+        assert!(server.functions.is_empty());
+        std::fs::write(&ignore_file, "").unwrap();
+        save_file(&mut server, &ignore_file);
+        assert!(server.functions.contains_key("Old"));
+        assert!(!server.functions.contains_key("Menu"));
+
+        // Files outside of the scar directory are ignored
+        let other_file = std::env::temp_dir().join("scardoc-lsp-other.scar");
+        std::fs::write(&other_file, "--? @shortdesc Other.\nfunction Other()\nend\n").unwrap();
+        let uri = super::get_file_uri(&other_file.display().to_string());
+        server.handle_message(&serde_json::json!({ "jsonrpc": "2.0", "method": "textDocument/didSave", "params": { "textDocument": { "uri": uri } } }));
+        assert!(!server.functions.contains_key("Other"));
+        let _ = std::fs::remove_file(&other_file);
+        let _ = std::fs::remove_dir_all(&scar_dir);
+    }
+
+    #[test]
+    fn can_link_see_references() {
+        let mut doc = crate::scardoc::generate_scardoc("scar").unwrap();
+        doc.get_function_mut("Util_ScarPos").unwrap().see = vec![String::from("TEAM_STATE.IDLE"), String::from("World_Pos"), String::from("Util_SpawnGroup")];
+        let server = super::ScarLanguageServer::new(doc);
+
+        // Functions and enum values link to their declaration, anything else is only quoted
+        let markdown = server.get_function_markdown(server.functions.get("Util_ScarPos").unwrap());
+        let links = format!("[`TEAM_STATE.IDLE`]({}#L5), `World_Pos`, [`Util_SpawnGroup`]({}#L8)",
+            super::get_file_uri("scar/enums.scar"), super::get_file_uri("scar/emmylua.scar"));
+        assert!(markdown.ends_with(&format!("\n\nSee also: {}", links)), "{}", markdown);
+    }
+
+    #[test]
+    fn can_handle_requests() {
+        let mut doc = crate::scardoc::generate_scardoc("scar").unwrap();
+        doc.get_function_mut("Util_ScarPos").unwrap().see = vec![String::from("Util_SpawnGroup"), String::from("Position")];
+        let mut server = super::ScarLanguageServer::new(doc);
+
+        let response = server.handle_message(&serde_json::json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }));
+        assert_eq!(response.unwrap()["result"]["capabilities"]["hoverProvider"], true);
+
+        server.handle_message(&serde_json::json!({ "jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {
+            "textDocument": { "uri": "file:///test.scar", "languageId": "lua", "version": 1, "text": "local pos = Util_ScarPos(1, " }
+        }}));
+
+        let position = serde_json::json!({ "textDocument": { "uri": "file:///test.scar" }, "position": { "line": 0, "character": 17 } });
+        let response = server.handle_message(&serde_json::json!({ "jsonrpc": "2.0", "id": 2, "method": "textDocument/hover", "params": position })).unwrap();
+        let hover = response["result"]["contents"]["value"].as_str().unwrap();
+        assert!(hover.contains("Util_ScarPos(Real xpos, Real zpos, Real ypos) -> Position"));
+        // References link to where they're declared, if they're declared in a scar file
+        let link = format!("[`Util_SpawnGroup`]({}#L8), `Position`", super::get_file_uri("scar/emmylua.scar"));
+        assert!(hover.ends_with(&format!("See also: {}", link)), "{}", hover);
+
+        let response = server.handle_message(&serde_json::json!({ "jsonrpc": "2.0", "id": 3, "method": "textDocument/definition", "params": position })).unwrap();
+        assert!(response["result"]["uri"].as_str().unwrap().ends_with("scar/simple.scar"));
+        assert_eq!(response["result"]["range"]["start"]["line"], 7);
+
+        let position = serde_json::json!({ "textDocument": { "uri": "file:///test.scar" }, "position": { "line": 0, "character": 28 } });
+        let response = server.handle_message(&serde_json::json!({ "jsonrpc": "2.0", "id": 4, "method": "textDocument/signatureHelp", "params": position })).unwrap();
+        assert_eq!(response["result"]["activeParameter"], 1);
+
+        let response = server.handle_message(&serde_json::json!({ "jsonrpc": "2.0", "id": 5, "method": "unknown/method" })).unwrap();
+        assert_eq!(response["error"]["code"], super::LSP_METHOD_NOT_FOUND);
+    }
+
+}
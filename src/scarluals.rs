@@ -0,0 +1,392 @@
+use std::{fs, io, path::Path, collections::BTreeSet, fmt::{self, Write}};
+
+use crate::{scarerror::ScarDocError, scardoc::{ScarDoc, ScarDocCategory, ScarGlobal}, scarfile::{ScarFunction, ScarParameter, ScarDeprecation, is_identifier}, scarenum::ScarEnum, scartype::{ScarType, is_primitive_type}};
+
+const LUA_KEYWORDS: [&str; 22] = [
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if",
+    "in", "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while"
+];
+
+/// Writes a set of Lua Language Server (`---@meta`) definition files describing the scardoc into `out_dir`.
+///
+/// One file is written per function category, plus `enums.lua`, `globals.lua` and `types.lua`.
+pub fn export_luals<P: AsRef<Path>>(doc: &ScarDoc, out_dir: P) -> Result<(), ScarDocError> {
+    let out_dir = out_dir.as_ref();
+    let write_file = |file_name: &str, content: Result<String, fmt::Error>| {
+        let path = out_dir.join(file_name);
+        content.map_err(io::Error::other)
+            .and_then(|x| fs::write(&path, x))
+            .map_err(|e| ScarDocError::io(path.display(), e))
+    };
+    fs::create_dir_all(out_dir).map_err(|e| ScarDocError::io(out_dir.display(), e))?;
+
+    let mut types = BTreeSet::new();
+
+    // Classes are the tables functions like 'SGroupCaller.Destroy' or 'Obj:Method' live in
+    let mut classes = BTreeSet::new();
+    for category in &doc.categories {
+        for func in &category.category_functions {
+            if let Some(idx) = func.name.find(['.', ':']) {
+                classes.insert(func.name[..idx].to_string());
+            }
+        }
+    }
+
+    for category in &doc.categories {
+        let content = write_category(category, &classes, &mut types);
+        write_file(&format!("{}.lua", get_file_name(&category.category_name)), content)?;
+    }
+
+    let enum_names: BTreeSet<&str> = doc.enums.iter().map(|x| x.name.as_str()).collect();
+    write_file("enums.lua", write_enums(&doc.enums))?;
+    write_file("globals.lua", write_globals(&doc.globals, &mut types))?;
+
+    write_file("types.lua", write_types(&doc.types, &mut types, &classes, &enum_names))?;
+
+    Ok(())
+}
+
+fn write_category(category: &ScarDocCategory, classes: &BTreeSet<String>, types: &mut BTreeSet<String>) -> Result<String, fmt::Error> {
+    let mut out = String::from("---@meta\n\n");
+    writeln!(out, "-- {}", category.category_name)?;
+    if let Some(description) = &category.category_description {
+        writeln!(out, "-- {}", description)?;
+    }
+    out.push('\n');
+    for func in &category.category_functions {
+        if !is_identifier_path(&func.name) {
+            continue;
+        }
+        write_function(&mut out, func, classes, types)?;
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn write_function(out: &mut String, func: &ScarFunction, classes: &BTreeSet<String>, types: &mut BTreeSet<String>) -> fmt::Result {
+    write_description(out, func.description_short.as_deref(), &func.description_extended)?;
+    write_notices(out, "function", func.deprecated.as_ref(), func.since.as_deref())?;
+
+    let mut arg_names = Vec::new();
+    for (i, param) in func.parameters.iter().enumerate() {
+        let name = get_param_name(param, i + 1);
+        let optional = if param.arg_required || name == "..." { "" } else { "?" };
+        write!(out, "---@param {}{} {}", name, optional, get_lua_type(&param.arg_type, types))?;
+        if let Some(desc) = &param.arg_description {
+            write!(out, " {}", desc)?;
+        }
+        if let Some(value) = &param.arg_default {
+            write!(out, " (default: `{}`)", value)?;
+        }
+        out.push('\n');
+        arg_names.push(name);
+    }
+
+    if let Some(ty) = func.return_type.as_deref().filter(|x| !is_void(x)) {
+        write!(out, "---@return {}", get_lua_type(ty, types))?;
+        if let Some(desc) = &func.return_description {
+            write!(out, " # {}", desc)?;
+        }
+        out.push('\n');
+    }
+    for name in &func.see {
+        writeln!(out, "---@see {}", name)?;
+    }
+
+    // Methods on tables we don't know about would be flagged as undefined globals
+    if let Some(idx) = func.name.find(['.', ':']) {
+        if !classes.contains(&func.name[..idx]) {
+            return Ok(());
+        }
+    }
+
+    writeln!(out, "function {}({}) end", func.name, arg_names.join(", "))?;
+    Ok(())
+}
+
+fn write_enums(enums: &[ScarEnum]) -> Result<String, fmt::Error> {
+    let mut out = String::from("---@meta\n\n");
+    for enum_def in enums {
+        if !is_identifier(&enum_def.name) {
+            continue;
+        }
+        write_description(&mut out, enum_def.description.as_deref(), &[])?;
+        write_notices(&mut out, "enum", enum_def.deprecated.as_ref(), enum_def.since.as_deref())?;
+        writeln!(out, "---@enum {}", enum_def.name)?;
+        writeln!(out, "{} = {{", enum_def.name)?;
+        for value in enum_def.values.iter().filter(|x| is_identifier(&x.name)) {
+            if let Some(desc) = value.description.as_deref() {
+                for ln in desc.lines() {
+                    writeln!(out, "\t--- {}", ln)?;
+                }
+            }
+            if let Some(deprecated) = &value.deprecated {
+                writeln!(out, "\t--- This value is {}", deprecated)?;
+                writeln!(out, "\t---@deprecated")?;
+            }
+            writeln!(out, "\t{} = {},", value.name, get_lua_literal(value.value.as_deref().unwrap_or("nil")))?;
+        }
+        writeln!(out, "}}\n")?;
+        // Enum values are also exposed as globals in scar, unless they're declared as fields of a table
+        if enum_def.table {
+            continue;
+        }
+        for value in enum_def.values.iter().filter(|x| is_identifier(&x.name)) {
+            writeln!(out, "---@type {}", enum_def.name)?;
+            writeln!(out, "{} = {}.{}", value.name, enum_def.name, value.name)?;
+        }
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn write_globals(globals: &[ScarGlobal], types: &mut BTreeSet<String>) -> Result<String, fmt::Error> {
+    let mut out = String::from("---@meta\n\n");
+    for global in globals {
+        // Dumps contain nested table paths (eg. 'PLAYERS.1.team') which aren't declarable
+        if !is_identifier(&global.name) {
+            continue;
+        }
+        let value = global.value.as_deref().unwrap_or("nil");
+        write_description(&mut out, global.description.as_deref(), &[])?;
+        write_notices(&mut out, "global", global.deprecated.as_ref(), global.since.as_deref())?;
+        let ty = match &global.global_type {
+            Some(ty) => get_lua_type(ty, types),
+            None => get_literal_type(value).to_string()
+        };
+        writeln!(out, "---@type {}", ty)?;
+        // Values of globals documented in scar files are Lua code, unless they're strings
+        let literal = match global.global_type.as_deref() {
+            Some(ty) if ty != "String" && global.value.is_some() => value.to_string(),
+            _ => get_lua_literal(value)
+        };
+        writeln!(out, "{} = {}\n", global.name, literal)?;
+    }
+    Ok(out)
+}
+
+fn write_types(doc_types: &[ScarType], types: &mut BTreeSet<String>, classes: &BTreeSet<String>, enum_names: &BTreeSet<&str>) -> Result<String, fmt::Error> {
+    let mut out = String::from("---@meta\n\n");
+
+    // Documented and engine types are declared with their fields, primitive types are Lua primitives
+    let mut documented = BTreeSet::new();
+    for ty in doc_types.iter().filter(|x| !(x.builtin && is_primitive_type(&x.name)) && is_identifier(&x.name)) {
+        write_description(&mut out, ty.description.as_deref(), &[])?;
+        match ty.parent.as_deref().filter(|x| is_identifier(x)) {
+            Some(parent) => {
+                types.insert(parent.to_string());
+                writeln!(out, "---@class {} : {}", ty.name, parent)?
+            }
+            None => writeln!(out, "---@class {}", ty.name)?
+        }
+        for field in ty.fields.iter().filter(|x| is_identifier(&x.name)) {
+            let field_type = get_lua_type(&field.field_type, types);
+            match &field.description {
+                Some(description) => writeln!(out, "---@field {} {} {}", field.name, field_type, description)?,
+                None => writeln!(out, "---@field {} {}", field.name, field_type)?
+            }
+        }
+        if classes.contains(&ty.name) {
+            writeln!(out, "{} = {{}}", ty.name)?;
+        }
+        out.push('\n');
+        documented.insert(ty.name.as_str());
+    }
+
+    // Declare every other referenced type that isn't a lua primitive, so the language server won't flag them as undefined
+    for ty in types.iter().filter(|x| !enum_names.contains(x.as_str()) && !classes.contains(*x) && !documented.contains(x.as_str())) {
+        writeln!(out, "---@class {}\n", ty)?;
+    }
+    for class in classes.iter().filter(|x| !documented.contains(x.as_str())) {
+        writeln!(out, "---@class {}", class)?;
+        writeln!(out, "{} = {{}}\n", class)?;
+    }
+    Ok(out)
+}
+
+fn write_description(out: &mut String, short: Option<&str>, extended: &[String]) -> fmt::Result {
+    // Descriptions of types and globals span several lines
+    if let Some(desc) = short {
+        for ln in desc.lines() {
+            writeln!(out, "--- {}", ln)?;
+        }
+    }
+    if !extended.is_empty() {
+        if short.is_some() {
+            writeln!(out, "---")?;
+        }
+        for ln in extended {
+            writeln!(out, "--- {}", ln)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the version something was introduced in and whether it's deprecated, which editors show by striking it through.
+fn write_notices(out: &mut String, kind: &str, deprecated: Option<&ScarDeprecation>, since: Option<&str>) -> fmt::Result {
+    if let Some(since) = since {
+        writeln!(out, "--- Since {}", since)?;
+    }
+    if let Some(deprecated) = deprecated {
+        writeln!(out, "--- This {} is {}", kind, deprecated)?;
+        writeln!(out, "---@deprecated")?;
+    }
+    Ok(())
+}
+
+fn get_param_name(param: &ScarParameter, index: usize) -> String {
+    if param.arg_name == "..." || (is_identifier(&param.arg_name) && !LUA_KEYWORDS.contains(&param.arg_name.as_str())) {
+        param.arg_name.clone()
+    } else {
+        format!("arg{}", index)
+    }
+}
+
+/// Maps a scar type name to its Lua Language Server equivalent, collecting non-primitive types in `types`.
+fn get_lua_type(scar_type: &str, types: &mut BTreeSet<String>) -> String {
+    let mapped: Vec<String> = scar_type.split(['|', '/'])
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+        .map(|x| match x.to_ascii_lowercase().as_str() {
+            "real" | "float" | "number" | "numeric" => String::from("number"),
+            "integer" | "int" => String::from("integer"),
+            "boolean" | "bool" => String::from("boolean"),
+            "string" | "str" => String::from("string"),
+            "luatable" | "table" => String::from("table"),
+            "luafunction" | "function" => String::from("function"),
+            "nil" | "void" => String::from("nil"),
+            "any" | "variant" | "" => String::from("any"),
+            _ if is_identifier(x) => {
+                types.insert(x.to_string());
+                x.to_string()
+            }
+            _ => String::from("any")
+        })
+        .collect();
+    if mapped.is_empty() {
+        String::from("any")
+    } else {
+        mapped.join("|")
+    }
+}
+
+fn get_literal_type(value: &str) -> &'static str {
+    if value == "true" || value == "false" {
+        "boolean"
+    } else if value.parse::<i64>().is_ok() {
+        "integer"
+    } else if value.parse::<f64>().is_ok_and(|x| x.is_finite()) && !value.chars().any(|c| c.is_ascii_alphabetic()) {
+        "number"
+    } else if value == "nil" {
+        "any"
+    } else {
+        "string"
+    }
+}
+
+fn get_lua_literal(value: &str) -> String {
+    match get_literal_type(value) {
+        "string" => format!("{:?}", value),
+        _ => value.to_string()
+    }
+}
+
+fn is_void(ty: &str) -> bool {
+    ty.eq_ignore_ascii_case("void") || ty.eq_ignore_ascii_case("nil")
+}
+
+fn is_identifier_path(name: &str) -> bool {
+    let parts: Vec<&str> = name.split(['.', ':']).collect();
+    parts.len() <= 2 && parts.iter().all(|x| is_identifier(x)) && name.matches(':').count() <= 1
+}
+
+fn get_file_name(category_name: &str) -> String {
+    category_name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' }).collect()
+}
+
+mod tests {
+
+    #[test]
+    fn can_map_scar_types() {
+        let mut types = std::collections::BTreeSet::new();
+        assert_eq!(super::get_lua_type("Real", &mut types), "number");
+        assert_eq!(super::get_lua_type("Boolean", &mut types), "boolean");
+        assert_eq!(super::get_lua_type("String|nil", &mut types), "string|nil");
+        assert_eq!(super::get_lua_type("SGroupID", &mut types), "SGroupID");
+        assert_eq!(super::get_lua_type("Table of things", &mut types), "any");
+        assert_eq!(types.len(), 1);
+    }
+
+    #[test]
+    fn can_write_function_definition() {
+        let doc = crate::scardoc::generate_scardoc("scar").unwrap();
+        let func = doc.get_function("Util_ScarPos").unwrap();
+        let mut out = String::new();
+        let mut types = std::collections::BTreeSet::new();
+        super::write_function(&mut out, func, &std::collections::BTreeSet::new(), &mut types).unwrap();
+        assert!(out.contains("---@param xpos number\n"));
+        assert!(out.contains("---@return Position\n"));
+        assert!(out.ends_with("function Util_ScarPos(xpos, zpos, ypos) end\n"));
+        assert!(types.contains("Position"));
+
+        let mut func = func.clone();
+        func.parameters[2].arg_required = false;
+        func.parameters[2].arg_default = Some(String::from("World_GetHeightAt(xpos, zpos)"));
+        let mut out = String::new();
+        super::write_function(&mut out, &func, &std::collections::BTreeSet::new(), &mut types).unwrap();
+        assert!(out.contains("---@param ypos? number (default: `World_GetHeightAt(xpos, zpos)`)\n"));
+
+        func.deprecated = Some(crate::scarfile::ScarDeprecation { reason: None, replacement: Some(String::from("Util_ScarPosition")) });
+        let mut out = String::new();
+        super::write_function(&mut out, &func, &std::collections::BTreeSet::new(), &mut types).unwrap();
+        assert!(out.contains("--- This function is deprecated, use Util_ScarPosition instead\n---@deprecated\n"));
+
+        func.see = vec![String::from("World_Pos")];
+        let mut out = String::new();
+        super::write_function(&mut out, &func, &std::collections::BTreeSet::new(), &mut types).unwrap();
+        assert!(out.contains("---@return Position\n---@see World_Pos\n"));
+    }
+
+    #[test]
+    fn can_write_globals() {
+        let scarfile = crate::scarfile::get_scar_sourcefile(String::from("scar/globals.scar")).unwrap();
+        let out = super::write_globals(&scarfile.globals, &mut std::collections::BTreeSet::new()).unwrap();
+        assert!(out.contains("---@type integer\nMAX_SQUADS = 5\n"));
+        assert!(out.contains("---@type string\nDEFAULT_BLUEPRINT = \"riflemen_us\"\n"));
+        // Functions spanning several lines are declared without their body
+        assert!(out.contains("---@type function\nOnStart = nil\n"));
+    }
+
+    #[test]
+    fn can_write_enums() {
+        let scarfile = crate::scarfile::get_scar_sourcefile(String::from("scar/enums.scar")).unwrap();
+        let out = super::write_enums(&scarfile.enums).unwrap();
+        assert!(out.contains("---@enum DIFFICULTIES\nDIFFICULTIES = {\n\tEASY = \"easy\",\n\tHARD = \"hard\",\n\tHARDEST = nil,\n}\n"), "{}", out);
+        // Values of tables are only reachable through the table
+        assert!(!out.contains("IDLE = TEAM_STATE.IDLE"));
+    }
+
+    #[test]
+    fn can_write_type_descriptions() {
+        let ty = crate::scartype::ScarType {
+            name: String::from("Position"),
+            description: Some(String::from("A position in the world.\nHeights are in metres.")),
+            parent: None,
+            fields: Vec::new(),
+            builtin: false
+        };
+        let mut types = std::collections::BTreeSet::new();
+        let out = super::write_types(&[ty], &mut types, &std::collections::BTreeSet::new(), &std::collections::BTreeSet::new()).unwrap();
+        assert!(out.contains("--- A position in the world.\n--- Heights are in metres.\n---@class Position\n"));
+    }
+
+    #[test]
+    fn can_write_engine_types() {
+        let mut types = std::collections::BTreeSet::new();
+        let out = super::write_types(&crate::scartype::get_builtin_types(), &mut types, &std::collections::BTreeSet::new(), &std::collections::BTreeSet::new()).unwrap();
+        assert!(out.contains("--- A group of squads\n---@class SGroupID\n"));
+        // Primitive types are Lua's own
+        assert!(!out.contains("---@class Real\n"));
+    }
+
+}
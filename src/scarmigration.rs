@@ -0,0 +1,121 @@
+use serde_json::Value;
+
+use crate::{scardoc::SCARDOC_FORMAT_VERSION, scarerror::ScarDocError};
+
+/// Migrations upgrading a scardoc from the version at their index to the next version.
+const MIGRATIONS: [fn(&mut Value); SCARDOC_FORMAT_VERSION as usize] = [
+    migrate_v0_to_v1,
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+    migrate_v3_to_v4,
+    migrate_v4_to_v5,
+    migrate_v5_to_v6,
+    migrate_v6_to_v7,
+    migrate_v7_to_v8
+];
+
+/// Gets the format version of a scardoc, unversioned scardocs being version 0.
+pub fn get_format_version(doc: &Value) -> u64 {
+    doc.get("format_version").and_then(|x| x.as_u64()).unwrap_or(0)
+}
+
+/// Fails if a scardoc read from `path` was written by a newer version of the generator.
+pub fn check_format_version(doc: &Value, path: &str) -> Result<u64, ScarDocError> {
+    match get_format_version(doc) {
+        version if version > SCARDOC_FORMAT_VERSION as u64 => Err(ScarDocError::UnsupportedVersion { path: path.to_string(), version }),
+        version => Ok(version)
+    }
+}
+
+/// Migrates a scardoc read from `path` and written by any earlier version of the generator to the current format version.
+pub fn migrate_scardoc(mut doc: Value, path: &str) -> Result<Value, ScarDocError> {
+    let version = check_format_version(&doc, path)?;
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(&mut doc);
+    }
+    if let Some(fields) = doc.as_object_mut() {
+        fields.insert(String::from("format_version"), Value::from(SCARDOC_FORMAT_VERSION));
+    }
+    Ok(doc)
+}
+
+/// Unversioned scardocs generated from dumps listed the first value of every enum twice.
+fn migrate_v0_to_v1(doc: &mut Value) {
+    for enum_def in doc.get_mut("enums").and_then(|x| x.as_array_mut()).into_iter().flatten() {
+        if let Some(values) = enum_def.get_mut("values").and_then(|x| x.as_array_mut()) {
+            let mut seen = Vec::new();
+            values.retain(|x| if seen.contains(x) { false } else { seen.push(x.clone()); true });
+        }
+    }
+}
+
+/// Version 2 added documented types, which older scardocs don't have.
+fn migrate_v1_to_v2(_doc: &mut Value) {}
+
+/// Version 3 added the default values of optional parameters.
+fn migrate_v2_to_v3(_doc: &mut Value) {}
+
+/// Version 4 added deprecation notices and the version things were introduced in.
+fn migrate_v3_to_v4(_doc: &mut Value) {}
+
+/// Version 5 added the references of functions listed by `@see`.
+fn migrate_v4_to_v5(_doc: &mut Value) {}
+
+/// Version 6 added enum descriptions and enums declared as Lua tables.
+fn migrate_v5_to_v6(_doc: &mut Value) {}
+
+/// Version 7 added the descriptions, deprecation notices and source locations of enum values.
+fn migrate_v6_to_v7(_doc: &mut Value) {}
+
+/// Version 8 added category descriptions.
+fn migrate_v7_to_v8(_doc: &mut Value) {}
+
+mod tests {
+
+    #[test]
+    fn can_migrate_unversioned_scardoc() {
+        let doc = serde_json::json!({
+            "enums": [{ "name": "CoverType", "values": [
+                { "name": "CT_None", "value": "0" },
+                { "name": "CT_None", "value": "0" },
+                { "name": "CT_Light", "value": "1" }
+            ]}]
+        });
+        let migrated = super::migrate_scardoc(doc, "scardoc.json").unwrap();
+        assert_eq!(migrated["format_version"], crate::scardoc::SCARDOC_FORMAT_VERSION);
+        assert_eq!(migrated["enums"][0]["values"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn can_migrate_scardoc_without_enums() {
+        let doc = serde_json::json!({ "categories": [] });
+        let migrated = super::migrate_scardoc(doc, "scardoc.json").unwrap();
+        assert!(migrated.get("enums").is_none());
+    }
+
+    #[test]
+    fn can_migrate_versioned_scardocs() {
+        // Versions after the first only added fields, which are kept as they are
+        let function = serde_json::json!({ "name": "Util_ScarPos", "deprecated": { "replacement": "Util_ScarPosition" }, "since": "1.1", "see": ["World_Pos"], "parameters": [
+            { "arg_name": "ypos", "arg_type": "Real", "arg_required": false, "arg_default": "0" }
+        ] });
+        let value = serde_json::json!({ "name": "IDLE", "value": "0", "description": "Not doing anything",
+            "deprecated": { "reason": "Unused" }, "source_file": "enums.scar", "source_line": 3 });
+        let enum_def = serde_json::json!({ "name": "TEAM_STATE", "description": "States of a team", "table": true, "values": [value] });
+        let doc = serde_json::json!({ "categories": [{ "category_name": "Util", "category_description": "Utility functions.", "category_functions": [function] }], "enums": [enum_def] });
+        for version in 1..crate::scardoc::SCARDOC_FORMAT_VERSION {
+            let mut versioned = doc.clone();
+            versioned["format_version"] = version.into();
+            let mut expected = doc.clone();
+            expected["format_version"] = crate::scardoc::SCARDOC_FORMAT_VERSION.into();
+            assert_eq!(super::migrate_scardoc(versioned, "scardoc.json").unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn refuses_newer_scardoc() {
+        let doc = serde_json::json!({ "format_version": crate::scardoc::SCARDOC_FORMAT_VERSION + 1 });
+        assert!(matches!(super::migrate_scardoc(doc, "scardoc.json"), Err(crate::scarerror::ScarDocError::UnsupportedVersion { .. })));
+    }
+
+}
@@ -0,0 +1,402 @@
+use std::{fs::{self, File}, io::BufReader, path::Path};
+use serde_json::{json, Value};
+
+use crate::{scardoc::SCARDOC_FORMAT_VERSION, scarerror::ScarDocError, scarmigration::check_format_version};
+
+pub const SCHEMA_ID: &str = "https://github.com/BattlegroundsCoH/scardoc-generator/scardoc.schema.json";
+
+/// Gets the JSON Schema describing the scardoc format.
+pub fn get_scardoc_schema() -> Value {
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": SCHEMA_ID,
+        "title": "ScarDoc",
+        "description": "Documentation of the functions, enums and globals available to scar scripts",
+        "type": "object",
+        "properties": {
+            "format_version": { "type": "integer", "minimum": 0, "maximum": SCARDOC_FORMAT_VERSION },
+            "categories": { "type": "array", "items": { "$ref": "#/$defs/ScarDocCategory" } },
+            "enums": { "type": "array", "items": { "$ref": "#/$defs/ScarEnum" } },
+            "globals": { "type": "array", "items": { "$ref": "#/$defs/ScarGlobal" } },
+            "types": { "type": "array", "items": { "$ref": "#/$defs/ScarType" } }
+        },
+        "additionalProperties": false,
+        "$defs": {
+            "ScarDocCategory": {
+                "type": "object",
+                "properties": {
+                    "category_name": { "type": "string" },
+                    "category_description": { "type": ["string", "null"] },
+                    "category_functions": { "type": "array", "items": { "$ref": "#/$defs/ScarFunction" } }
+                },
+                "required": ["category_name", "category_functions"],
+                "additionalProperties": false
+            },
+            "ScarFunction": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "description_short": { "type": ["string", "null"] },
+                    "description_extended": { "type": "array", "items": { "type": "string" } },
+                    "example": { "type": ["string", "null"] },
+                    "return_description": { "type": ["string", "null"] },
+                    "return_type": { "type": ["string", "null"] },
+                    "parameters": { "type": "array", "items": { "$ref": "#/$defs/ScarParameter" } },
+                    "source_file": { "type": ["string", "null"] },
+                    "source_line": { "type": ["integer", "null"], "minimum": 0 },
+                    "groups": { "type": "array", "items": { "type": "string" } },
+                    "deprecated": { "$ref": "#/$defs/ScarDeprecation" },
+                    "since": { "type": ["string", "null"] },
+                    "see": { "type": "array", "items": { "type": "string" } }
+                },
+                "required": ["name"],
+                "additionalProperties": false
+            },
+            "ScarDeprecation": {
+                "type": ["object", "null"],
+                "properties": {
+                    "reason": { "type": ["string", "null"] },
+                    "replacement": { "type": ["string", "null"] }
+                },
+                "additionalProperties": false
+            },
+            "ScarParameter": {
+                "type": "object",
+                "properties": {
+                    "arg_name": { "type": "string" },
+                    "arg_type": { "type": "string" },
+                    "arg_description": { "type": ["string", "null"] },
+                    "arg_required": { "type": "boolean" },
+                    "arg_default": { "type": ["string", "null"] }
+                },
+                "required": ["arg_name", "arg_type", "arg_required"],
+                "additionalProperties": false
+            },
+            "ScarEnum": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "values": { "type": "array", "items": { "$ref": "#/$defs/ScarEnumValue" } },
+                    "description": { "type": ["string", "null"] },
+                    "deprecated": { "$ref": "#/$defs/ScarDeprecation" },
+                    "since": { "type": ["string", "null"] },
+                    "table": { "type": "boolean" }
+                },
+                "required": ["name", "values"],
+                "additionalProperties": false
+            },
+            "ScarEnumValue": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "value": { "type": ["string", "null"] },
+                    "description": { "type": ["string", "null"] },
+                    "deprecated": { "$ref": "#/$defs/ScarDeprecation" },
+                    "source_file": { "type": ["string", "null"] },
+                    "source_line": { "type": ["integer", "null"], "minimum": 0 }
+                },
+                "required": ["name"],
+                "additionalProperties": false
+            },
+            "ScarGlobal": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "value": { "type": ["string", "null"] },
+                    "description": { "type": ["string", "null"] },
+                    "global_type": { "type": ["string", "null"] },
+                    "deprecated": { "$ref": "#/$defs/ScarDeprecation" },
+                    "since": { "type": ["string", "null"] }
+                },
+                "required": ["name"],
+                "additionalProperties": false
+            },
+            "ScarType": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "description": { "type": ["string", "null"] },
+                    "parent": { "type": ["string", "null"] },
+                    "fields": { "type": "array", "items": { "$ref": "#/$defs/ScarTypeField" } },
+                    "builtin": { "type": "boolean" }
+                },
+                "required": ["name"],
+                "additionalProperties": false
+            },
+            "ScarTypeField": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "field_type": { "type": "string" },
+                    "description": { "type": ["string", "null"] }
+                },
+                "required": ["name", "field_type"],
+                "additionalProperties": false
+            }
+        }
+    })
+}
+
+/// Writes the JSON Schema describing the scardoc format to `out_file`.
+pub fn save_scardoc_schema<P: AsRef<Path>>(out_file: P) -> Result<(), ScarDocError> {
+    let path = out_file.as_ref().display();
+    let json = serde_json::to_string_pretty(&get_scardoc_schema()).map_err(|e| ScarDocError::json(&path, e))?;
+    fs::write(out_file.as_ref(), json + "\n").map_err(|e| ScarDocError::io(&path, e))
+}
+
+/// Validates a JSON file against the scardoc schema, returning every violation found.
+///
+/// Scardocs written by a newer version of the generator are refused, as the schema can't describe what they add.
+pub fn validate_scardoc_file<P: AsRef<Path>>(file_path: P) -> Result<Vec<String>, ScarDocError> {
+    let path = file_path.as_ref().display();
+    let reader = BufReader::new(File::open(file_path.as_ref()).map_err(|e| ScarDocError::io(&path, e))?);
+    let value: Value = serde_json::from_reader(reader).map_err(|e| ScarDocError::json(&path, e))?;
+    check_format_version(&value, &path.to_string())?;
+    Ok(validate_scardoc(&value))
+}
+
+/// Validates a JSON value against the scardoc schema, returning every violation found.
+pub fn validate_scardoc(value: &Value) -> Vec<String> {
+    let schema = get_scardoc_schema();
+    let mut errors = Vec::new();
+    validate(&schema, &schema, value, "$", &mut errors);
+    errors
+}
+
+/// Validates `value` against the subset of JSON Schema used by the scardoc schema.
+fn validate(root: &Value, schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+
+    if let Some(reference) = schema["$ref"].as_str() {
+        match reference.strip_prefix("#/$defs/").map(|x| &root["$defs"][x]) {
+            Some(def) if !def.is_null() => validate(root, def, value, path, errors),
+            _ => errors.push(format!("{}: unresolved schema reference '{}'", path, reference))
+        }
+        return;
+    }
+
+    let types: Vec<&str> = match &schema["type"] {
+        Value::String(s) => vec![s.as_str()],
+        Value::Array(a) => a.iter().filter_map(|x| x.as_str()).collect(),
+        _ => Vec::new()
+    };
+    if !types.is_empty() && !types.iter().any(|x| is_type(value, x)) {
+        errors.push(format!("{}: expected {} but found {}", path, types.join(" or "), get_type(value)));
+        return;
+    }
+
+    if let (Some(minimum), Some(number)) = (schema["minimum"].as_f64(), value.as_f64()) {
+        if number < minimum {
+            errors.push(format!("{}: {} is less than the minimum of {}", path, number, minimum));
+        }
+    }
+    if let (Some(maximum), Some(number)) = (schema["maximum"].as_f64(), value.as_f64()) {
+        if number > maximum {
+            errors.push(format!("{}: {} is more than the maximum of {}", path, number, maximum));
+        }
+    }
+
+    if let Value::Array(items) = value {
+        if schema.get("items").is_some() {
+            for (i, item) in items.iter().enumerate() {
+                validate(root, &schema["items"], item, &format!("{}[{}]", path, i), errors);
+            }
+        }
+    }
+
+    if let Value::Object(fields) = value {
+        for required in schema["required"].as_array().into_iter().flatten().filter_map(|x| x.as_str()) {
+            if !fields.contains_key(required) {
+                errors.push(format!("{}: missing required property '{}'", path, required));
+            }
+        }
+        for (key, field) in fields {
+            match schema["properties"].get(key) {
+                Some(property) => validate(root, property, field, &format!("{}.{}", path, key), errors),
+                None => if schema["additionalProperties"] == false {
+                    errors.push(format!("{}: unknown property '{}'", path, key));
+                }
+            }
+        }
+    }
+
+}
+
+fn is_type(value: &Value, ty: &str) -> bool {
+    match ty {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => false
+    }
+}
+
+fn get_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object"
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn shipped_schema_is_up_to_date() {
+        let shipped: serde_json::Value = serde_json::from_str(include_str!("../scardoc.schema.json")).unwrap();
+        assert_eq!(shipped, super::get_scardoc_schema(), "scardoc.schema.json is outdated, regenerate it with the 'schema' command");
+    }
+
+    #[test]
+    fn generated_scardoc_is_valid() {
+        let doc = crate::scardoc::generate_scardoc("scar").unwrap();
+        let value = serde_json::to_value(&doc).unwrap();
+        assert_eq!(super::validate_scardoc(&value), Vec::<String>::new());
+    }
+
+    #[test]
+    fn populated_scardoc_matches_schema() {
+        /// Gets a scardoc with every optional field set, so every property of the schema is serialised.
+        fn get_populated_scardoc() -> crate::scardoc::ScarDoc {
+            use crate::{scardoc::*, scarfile::*, scarenum::*, scartype::*};
+            let get_deprecation = || Some(ScarDeprecation { reason: Some(String::from("Replaced")), replacement: Some(String::from("World_Pos")) });
+            ScarDoc {
+                format_version: SCARDOC_FORMAT_VERSION,
+                categories: vec![ScarDocCategory {
+                    category_name: String::from("Util"),
+                    category_description: Some(String::from("Utility functions")),
+                    category_functions: vec![ScarFunction {
+                        name: String::from("Util_ScarPos"),
+                        description_short: Some(String::from("Converts a 2D position.")),
+                        description_extended: vec![String::from("The height is looked up.")],
+                        example: Some(String::from("Util_ScarPos(0, 0)")),
+                        return_description: Some(String::from("The position")),
+                        return_type: Some(String::from("Position")),
+                        parameters: vec![ScarParameter {
+                            arg_name: String::from("ypos"),
+                            arg_type: String::from("Real"),
+                            arg_description: Some(String::from("The height")),
+                            arg_required: false,
+                            arg_default: Some(String::from("0"))
+                        }],
+                        source_file: Some(String::from("scar/simple.scar")),
+                        source_line: Some(8),
+                        groups: vec![String::from("Util")],
+                        deprecated: get_deprecation(),
+                        since: Some(String::from("1.0")),
+                        see: vec![String::from("World_Pos")]
+                    }]
+                }],
+                enums: vec![ScarEnum {
+                    name: String::from("TEAM_STATE"),
+                    values: vec![ScarEnumValue {
+                        name: String::from("IDLE"),
+                        value: Some(String::from("0")),
+                        description: Some(String::from("Not doing anything")),
+                        deprecated: get_deprecation(),
+                        source_file: Some(String::from("scar/enums.scar")),
+                        source_line: Some(5)
+                    }],
+                    description: Some(String::from("The states of a team")),
+                    deprecated: get_deprecation(),
+                    since: Some(String::from("1.0")),
+                    table: true
+                }],
+                globals: vec![ScarGlobal {
+                    name: String::from("MAX_SQUADS"),
+                    value: Some(String::from("5")),
+                    description: Some(String::from("The most squads")),
+                    global_type: Some(String::from("Integer")),
+                    deprecated: get_deprecation(),
+                    since: Some(String::from("1.0"))
+                }],
+                types: vec![ScarType {
+                    name: String::from("Position"),
+                    description: Some(String::from("A position")),
+                    parent: Some(String::from("LuaTable")),
+                    fields: vec![ScarTypeField {
+                        name: String::from("x"),
+                        field_type: String::from("Real"),
+                        description: Some(String::from("The x coordinate"))
+                    }],
+                    builtin: true
+                }]
+            }
+        }
+
+        fn get_property_names(value: &serde_json::Value, names: &mut std::collections::BTreeSet<String>) {
+            match value {
+                serde_json::Value::Object(fields) => for (key, field) in fields {
+                    names.insert(key.clone());
+                    get_property_names(field, names);
+                },
+                serde_json::Value::Array(items) => for item in items {
+                    get_property_names(item, names);
+                },
+                _ => {}
+            }
+        }
+
+        let value = serde_json::to_value(get_populated_scardoc()).unwrap();
+        assert_eq!(super::validate_scardoc(&value), Vec::<String>::new());
+
+        // Every property of the schema must also be a field of the scardoc, so neither can drift from the other
+        let schema = super::get_scardoc_schema();
+        let mut schema_names = std::collections::BTreeSet::new();
+        for properties in std::iter::once(&schema["properties"]).chain(schema["$defs"].as_object().unwrap().values().map(|x| &x["properties"])) {
+            schema_names.extend(properties.as_object().unwrap().keys().cloned());
+        }
+        let mut names = std::collections::BTreeSet::new();
+        get_property_names(&value, &mut names);
+        assert_eq!(names, schema_names);
+    }
+
+    #[test]
+    fn can_find_violations() {
+        let value = serde_json::json!({
+            "categories": [{ "category_name": "Util", "category_functions": [
+                { "name": "Util_ScarPos", "parameters": [{ "arg_name": "xpos", "arg_typ": "Real", "arg_required": "yes" }] }
+            ]}],
+            "enums": {}
+        });
+        let errors = super::validate_scardoc(&value);
+        assert_eq!(errors, vec![
+            "$.categories[0].category_functions[0].parameters[0]: missing required property 'arg_type'",
+            "$.categories[0].category_functions[0].parameters[0].arg_required: expected boolean but found string",
+            "$.categories[0].category_functions[0].parameters[0]: unknown property 'arg_typ'",
+            "$.enums: expected array but found object"
+        ]);
+    }
+
+    #[test]
+    fn allows_null_deprecations() {
+        // Like other optional fields, deprecations may be null in hand-written scardocs
+        let value = serde_json::json!({
+            "categories": [{ "category_name": "Util", "category_functions": [{ "name": "Util_ScarPos", "deprecated": null }] }],
+            "enums": [{ "name": "TEAM_STATE", "deprecated": null, "values": [{ "name": "IDLE", "deprecated": null }] }],
+            "globals": [{ "name": "MAX_SQUADS", "deprecated": null }]
+        });
+        assert!(super::validate_scardoc(&value).is_empty());
+        assert!(serde_json::from_value::<crate::scardoc::ScarDoc>(value).is_ok());
+    }
+
+    #[test]
+    fn refuses_newer_scardoc_file() {
+        let value = serde_json::json!({ "format_version": crate::scardoc::SCARDOC_FORMAT_VERSION + 1, "categories": [], "future": true });
+        assert!(super::validate_scardoc(&value).contains(&format!("$.format_version: {} is more than the maximum of {}",
+            crate::scardoc::SCARDOC_FORMAT_VERSION + 1, crate::scardoc::SCARDOC_FORMAT_VERSION)));
+
+        let file = std::env::temp_dir().join(format!("scardoc-schema-test-{}.json", std::process::id()));
+        std::fs::write(&file, value.to_string()).unwrap();
+        assert!(matches!(super::validate_scardoc_file(&file), Err(crate::scarerror::ScarDocError::UnsupportedVersion { .. })));
+        std::fs::remove_file(&file).unwrap();
+    }
+
+}
@@ -0,0 +1,72 @@
+use std::{fs, path::Path, collections::BTreeMap};
+use serde::Serialize;
+
+use crate::{scarerror::ScarDocError, scardoc::ScarDoc, scarfile::ScarFunction};
+
+#[derive(Serialize)]
+pub struct ScarSnippet {
+    pub prefix: String,
+    pub body: String,
+    pub description: String,
+    pub scope: String
+}
+
+/// Writes a VS Code `.code-snippets` file with a snippet for every function in the scardoc.
+pub fn export_snippets<P: AsRef<Path>>(doc: &ScarDoc, out_file: P) -> Result<(), ScarDocError> {
+    let path = out_file.as_ref().display();
+    let json = serde_json::to_string_pretty(&get_snippets(doc)).map_err(|e| ScarDocError::json(&path, e))?;
+    fs::write(out_file.as_ref(), json).map_err(|e| ScarDocError::io(&path, e))
+}
+
+pub fn get_snippets(doc: &ScarDoc) -> BTreeMap<String, ScarSnippet> {
+    doc.categories.iter()
+        .flat_map(|x| x.category_functions.iter())
+        .map(|x| (x.name.clone(), get_snippet(x)))
+        .collect()
+}
+
+fn get_snippet(func: &ScarFunction) -> ScarSnippet {
+    // Each parameter becomes a tab stop, optional ones are shown in brackets like in the @args directive
+    let args: Vec<String> = func.parameters.iter()
+        .enumerate()
+        .map(|(i, x)| {
+            let name = escape_placeholder(&x.arg_name);
+            if x.arg_required {
+                format!("${{{}:{}}}", i + 1, name)
+            } else {
+                format!("${{{}:[{}]}}", i + 1, name)
+            }
+        })
+        .collect();
+    ScarSnippet {
+        prefix: func.name.clone(),
+        body: format!("{}({})$0", escape_body(&func.name), args.join(", ")),
+        description: match &func.deprecated {
+            Some(deprecated) => format!("{} ({})", func.description_short.as_ref().unwrap_or(&func.name), deprecated),
+            None => func.description_short.clone().unwrap_or_else(|| func.name.clone())
+        },
+        scope: String::from("lua")
+    }
+}
+
+fn escape_body(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('$', "\\$")
+}
+
+fn escape_placeholder(text: &str) -> String {
+    escape_body(text).replace('}', "\\}")
+}
+
+mod tests {
+
+    #[test]
+    fn can_get_function_snippet() {
+        let doc = crate::scardoc::generate_scardoc("scar").unwrap();
+        let snippets = super::get_snippets(&doc);
+        let snippet = snippets.get("Util_SpawnGroup").unwrap();
+        assert_eq!(snippet.prefix, "Util_SpawnGroup");
+        assert_eq!(snippet.body, "Util_SpawnGroup(${1:name}, ${2:pos}, ${3:[count]})$0");
+        assert_eq!(snippet.description, "Creates a squad group and spawns it at a position.");
+    }
+
+}
@@ -0,0 +1,78 @@
+use serde::{Serialize, Deserialize};
+
+/// The scar primitive types, documented by every generated scardoc.
+const BUILTIN_TYPES: [(&str, &str); 8] = [
+    ("Real", "A floating point number"),
+    ("Integer", "A whole number"),
+    ("Boolean", "Either true or false"),
+    ("String", "A string of text"),
+    ("LuaTable", "A Lua table"),
+    ("LuaFunction", "A Lua function"),
+    ("Any", "A value of any type"),
+    ("Void", "No value")
+];
+
+/// The handles and IDs of SCAR engine objects, documented by every generated scardoc unless a scar file documents them.
+const ENGINE_TYPES: [(&str, &str); 22] = [
+    ("SGroupID", "A group of squads"),
+    ("EGroupID", "A group of entities"),
+    ("SquadID", "A squad in the world"),
+    ("EntityID", "An entity in the world"),
+    ("PlayerID", "A player in the game"),
+    ("Position", "A position in the world"),
+    ("MarkerID", "A marker placed in the world editor"),
+    ("ModID", "A modifier applied to a game object"),
+    ("SectorID", "A territory sector"),
+    ("HintPointID", "A hint point shown to the player"),
+    ("ObjectiveID", "An objective shown to the player"),
+    ("EventCueID", "An event cue shown to the player"),
+    ("ScarBlueprint", "A blueprint from the attribute editor"),
+    ("SquadBlueprint", "The blueprint of a squad"),
+    ("EntityBlueprint", "The blueprint of an entity"),
+    ("AbilityBlueprint", "The blueprint of an ability"),
+    ("UpgradeBlueprint", "The blueprint of an upgrade"),
+    ("WeaponBlueprint", "The blueprint of a weapon"),
+    ("CriticalBlueprint", "The blueprint of a critical"),
+    ("SlotItemBlueprint", "The blueprint of a slot item"),
+    ("SBP", "The blueprint of a squad"),
+    ("EBP", "The blueprint of an entity")
+];
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScarType {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<ScarTypeField>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub builtin: bool
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScarTypeField {
+    pub name: String,
+    pub field_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>
+}
+
+/// Gets the scar primitive types and the SCAR engine types.
+pub fn get_builtin_types() -> Vec<ScarType> {
+    BUILTIN_TYPES.iter().chain(ENGINE_TYPES.iter())
+        .map(|(name, description)| ScarType {
+            name: name.to_string(),
+            description: Some(description.to_string()),
+            parent: None,
+            fields: Vec::new(),
+            builtin: true
+        })
+        .collect()
+}
+
+/// Checks whether a type is one of the scar primitive types.
+pub(crate) fn is_primitive_type(name: &str) -> bool {
+    BUILTIN_TYPES.iter().any(|(x, _)| *x == name)
+}
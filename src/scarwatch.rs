@@ -0,0 +1,171 @@
+use std::{collections::BTreeSet, path::{Path, PathBuf}, sync::mpsc::{self, Receiver, RecvTimeoutError}, time::Duration};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+use crate::scarcache::{ScarCache, read_scar_files_cached};
+use crate::scardoc::{GenerateOptions, get_filtered_scar_files, get_scardoc_from_sources, save_scardoc_to_json};
+use crate::scarfilter::{SCARDOC_IGNORE_FILE, ScarFileMatcher};
+use crate::scarcategory::Categoriser;
+use crate::scarerror::ScarDocError;
+use crate::{scarluals::export_luals, scarsnippets::export_snippets};
+
+/// Options for regenerating a scardoc whenever a scar directory changes.
+#[derive(Clone)]
+pub struct WatchOptions {
+    pub generate: GenerateOptions,
+    /// The file the scardoc is saved to.
+    pub output: PathBuf,
+    /// The directory Lua Language Server definitions are exported to, if any.
+    pub luals: Option<PathBuf>,
+    /// The file VS Code snippets are exported to, if any.
+    pub snippets: Option<PathBuf>,
+    /// How long to wait for further changes before regenerating, so a burst of saves only regenerates once.
+    pub debounce: Duration
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        WatchOptions {
+            generate: GenerateOptions::default(),
+            output: PathBuf::from("scardoc.json"),
+            luals: None,
+            snippets: None,
+            debounce: Duration::from_millis(300)
+        }
+    }
+}
+
+/// Generates the scardoc and exports for `dir_path`, then regenerates them every time a scar file in it, or its
+/// `.scardocignore` file, changes.
+///
+/// Unchanged files are kept in memory between runs, so only the warnings of the changed files are printed.
+/// Runs until the directory can no longer be watched.
+pub fn watch_scardoc<P: AsRef<Path>>(dir_path: P, options: &WatchOptions) -> Result<(), ScarDocError> {
+    let dir = dir_path.as_ref();
+    let path = dir.display();
+
+    // Uses inotify on Linux
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(|e| ScarDocError::Watch { path: path.to_string(), source: e })?;
+    watcher.watch(dir, RecursiveMode::Recursive).map_err(|e| ScarDocError::Watch { path: path.to_string(), source: e })?;
+
+    let categoriser = options.generate.categories.get_categoriser()?;
+    let mut cache = options.generate.cache.as_ref().map_or_else(ScarCache::default, ScarCache::load);
+    if let Err(e) = regenerate(dir, options, &categoriser, &mut cache) {
+        eprintln!("{}", e);
+    }
+
+    let mut matcher = options.generate.filter.get_matcher(dir)?;
+
+    println!("Watching {} for changes", path);
+    while let Some(changed) = wait_for_changes(&rx, options.debounce, |x| is_watched(dir, &matcher, x)) {
+        for file in &changed {
+            println!("Changed: {}", file.display());
+        }
+        // Files the new ignore file no longer skips have to be watched from now on, an invalid one keeps the old matcher
+        if changed.iter().any(|x| x.strip_prefix(dir).unwrap_or(x) == Path::new(SCARDOC_IGNORE_FILE)) {
+            match options.generate.filter.get_matcher(dir) {
+                Ok(new_matcher) => matcher = new_matcher,
+                Err(e) => eprintln!("{}", e)
+            }
+        }
+        if let Err(e) = regenerate(dir, options, &categoriser, &mut cache) {
+            eprintln!("{}", e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks if a change to `path` should regenerate the scardoc.
+fn is_watched(dir_path: &Path, matcher: &ScarFileMatcher, path: &Path) -> bool {
+    let file_path = path.strip_prefix(dir_path).unwrap_or(path);
+    matcher.is_match(file_path) || file_path == Path::new(SCARDOC_IGNORE_FILE)
+}
+
+fn regenerate(dir_path: &Path, options: &WatchOptions, categoriser: &Categoriser, cache: &mut ScarCache) -> Result<(), ScarDocError> {
+    let files = get_filtered_scar_files(dir_path, &options.generate.filter)?;
+    let sources = read_scar_files_cached(&files, options.generate.jobs, cache);
+    if let Some(cache_path) = &options.generate.cache {
+        if let Err(e) = cache.save(cache_path) {
+            eprintln!("Failed saving scar cache: {}", e);
+        }
+    }
+
+    let doc = get_scardoc_from_sources(sources, options.generate.order, categoriser);
+    save_scardoc_to_json(&doc, &options.output.display().to_string())?;
+    if let Some(out_dir) = &options.luals {
+        export_luals(&doc, out_dir)?;
+    }
+    if let Some(out_file) = &options.snippets {
+        export_snippets(&doc, out_file)?;
+    }
+
+    println!("Saved scardoc to {}", options.output.display());
+    Ok(())
+}
+
+/// Waits for watched files to change, then keeps collecting changes until none are made for `debounce`.
+///
+/// Returns the changed files, or `None` once the watcher has stopped.
+fn wait_for_changes<F: Fn(&Path) -> bool>(rx: &Receiver<notify::Result<Event>>, debounce: Duration, is_watched: F) -> Option<BTreeSet<PathBuf>> {
+    let mut changed = BTreeSet::new();
+    loop {
+        let event = if changed.is_empty() {
+            rx.recv().ok()?
+        } else {
+            match rx.recv_timeout(debounce) {
+                Ok(event) => event,
+                Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => return Some(changed)
+            }
+        };
+        match event {
+            Err(e) => eprintln!("Failed watching for changes: {}", e),
+            Ok(event) => if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
+                changed.extend(event.paths.into_iter().filter(|x| is_watched(x)));
+            }
+        }
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn can_debounce_changes() {
+        use notify::{Event, EventKind, event::{ModifyKind, AccessKind}};
+        let (tx, rx) = std::sync::mpsc::channel();
+        let event = |kind, path: &str| Ok(Event::new(kind).add_path(path.into()));
+        tx.send(event(EventKind::Modify(ModifyKind::Any), "scar/simple.scar")).unwrap();
+        tx.send(event(EventKind::Modify(ModifyKind::Any), "scar/notes.txt")).unwrap();
+        tx.send(event(EventKind::Access(AccessKind::Any), "scar/emmylua.scar")).unwrap();
+        tx.send(event(EventKind::Modify(ModifyKind::Any), "scar/simple.scar")).unwrap();
+
+        // A burst of changes is reported once, without other files and reads
+        let is_scar_file = |x: &std::path::Path| x.extension() == Some("scar".as_ref());
+        let changed = super::wait_for_changes(&rx, std::time::Duration::from_millis(10), is_scar_file).unwrap();
+        assert_eq!(changed.into_iter().collect::<Vec<_>>(), vec![std::path::PathBuf::from("scar/simple.scar")]);
+
+        drop(tx);
+        assert!(super::wait_for_changes(&rx, std::time::Duration::from_millis(10), is_scar_file).is_none());
+    }
+
+    #[test]
+    fn can_watch_ignored_files() {
+        use std::path::Path;
+        let dir = std::env::temp_dir().join(format!("scardoc-watch-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let filter = crate::scarfilter::ScarFileFilter::default();
+        std::fs::write(dir.join(crate::scarfilter::SCARDOC_IGNORE_FILE), "_old/\n").unwrap();
+        let matcher = filter.get_matcher(&dir).unwrap();
+        assert!(!super::is_watched(&dir, &matcher, &dir.join("_old/simple.scar")));
+        assert!(super::is_watched(&dir, &matcher, &dir.join(crate::scarfilter::SCARDOC_IGNORE_FILE)));
+
+        // The rebuilt matcher watches files the ignore file no longer skips
+        std::fs::write(dir.join(crate::scarfilter::SCARDOC_IGNORE_FILE), "").unwrap();
+        let matcher = filter.get_matcher(&dir).unwrap();
+        assert!(super::is_watched(&dir, &matcher, &dir.join("_old/simple.scar")));
+        assert!(!super::is_watched(&dir, &matcher, Path::new("notes.txt")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+}